@@ -1,19 +1,28 @@
 use crate::{
     conf::Server,
-    print::{DiskUsage, Output, Print, Tabulate},
+    print::{DiskUsage, Output, PagePrinter, Print, Tabulate},
 };
 
-use fstore::{http, ObjectError, Uuid};
-use std::{error::Error, path::PathBuf, result};
+use fstore::{
+    http::{self, UploadOptions},
+    ArchiveFormat, BucketPatch, LabelSelector, MetadataPatch, ObjectError,
+    ObjectQuery, Uuid,
+};
+use std::{error::Error, fs, path::PathBuf, pin::pin, result};
 use tokio::{
     fs::File,
-    io::{stdin, stdout},
+    io::{stdin, stdout, AsyncRead},
+    time::{sleep, Duration},
 };
+use tokio_stream::StreamExt;
 use tokio_util::io::StreamReader;
 
 pub type BoxError = Box<dyn Error + Send + Sync + 'static>;
 pub type Result = result::Result<(), BoxError>;
 
+const UPLOAD_RETRIES: u32 = 3;
+const UPLOAD_RETRY_DELAY: Duration = Duration::from_secs(1);
+
 #[derive(Clone, Debug)]
 pub struct Client {
     client: http::Client,
@@ -21,11 +30,14 @@ pub struct Client {
 }
 
 impl Client {
-    pub fn new(server: &Server, output: Output) -> Self {
-        Self {
-            client: http::Client::new(&server.url),
-            output,
-        }
+    pub fn new(
+        server: &Server,
+        output: Output,
+    ) -> result::Result<Self, String> {
+        let client = build_client(server)
+            .map_err(|err| format!("failed to create client: {err}"))?;
+
+        Ok(Self { client, output })
     }
 
     pub async fn about(&self) -> Result {
@@ -59,6 +71,14 @@ impl Client {
         Ok(())
     }
 
+    pub async fn abort_uploads(&self, parts: Vec<Uuid>) -> Result {
+        for part in parts {
+            self.client.remove_part(part).await?;
+        }
+
+        Ok(())
+    }
+
     pub async fn add_bucket(&self, name: String) -> Result {
         let bucket = self.client.add_bucket(&name).await?;
 
@@ -75,23 +95,53 @@ impl Client {
         Ok(())
     }
 
-    pub async fn get_all_objects(&self, bucket: Uuid) -> Result {
+    pub async fn export(
+        &self,
+        bucket: Uuid,
+        objects: &[Uuid],
+        format: ArchiveFormat,
+        destination: Option<PathBuf>,
+    ) -> Result {
+        let objects = (!objects.is_empty()).then_some(objects);
+        let stream = self.client.export(bucket, format, objects).await?;
+        write_stream(StreamReader::new(stream), destination).await
+    }
+
+    pub async fn get_bucket(&self, name: String) -> Result {
+        self.client.get_bucket(&name).await?.1.print(self.output);
+
+        Ok(())
+    }
+
+    pub async fn get_buckets(&self, selector: &LabelSelector) -> Result {
         self.client
-            .get_all_objects(bucket)
+            .get_buckets_matching(selector)
             .await?
             .print(self.output);
 
         Ok(())
     }
 
-    pub async fn get_bucket(&self, name: String) -> Result {
-        self.client.get_bucket(&name).await?.1.print(self.output);
-
-        Ok(())
+    pub async fn get_key(
+        &self,
+        bucket: Uuid,
+        key: &str,
+        destination: Option<PathBuf>,
+    ) -> Result {
+        let stream = self.client.get_key_stream(bucket, key).await?;
+        write_stream(StreamReader::new(stream), destination).await
     }
 
-    pub async fn get_buckets(&self) -> Result {
-        self.client.get_buckets().await?.print(self.output);
+    pub async fn get_keys(
+        &self,
+        bucket: Uuid,
+        prefix: &str,
+        delimiter: Option<&str>,
+    ) -> Result {
+        self.client
+            .get_keys(bucket, prefix, delimiter)
+            .await?
+            .print(self.output);
 
         Ok(())
     }
@@ -103,40 +153,14 @@ impl Client {
         destination: Option<PathBuf>,
     ) -> Result {
         let stream = self.client.get_object_stream(bucket, object).await?;
-        let mut reader = StreamReader::new(stream);
+        write_stream(StreamReader::new(stream), destination).await
+    }
 
-        match destination {
-            Some(path) => {
-                let mut file = File::options()
-                    .create(true)
-                    .write(true)
-                    .truncate(true)
-                    .open(&path)
-                    .await
-                    .map_err(|err| {
-                        format!(
-                            "Failed to open file for writing '{}': {err}",
-                            path.display()
-                        )
-                    })?;
-
-                tokio::io::copy(&mut reader, &mut file).await.map_err(
-                    |err| {
-                        format!(
-                            "Failed to stream object data to file '{}': {err}",
-                            path.display()
-                        )
-                    },
-                )?;
-            }
-            None => {
-                tokio::io::copy(&mut reader, &mut stdout()).await.map_err(
-                    |err| {
-                        format!("Failed to stream object data to STDOUT: {err}")
-                    },
-                )?;
-            }
-        }
+    pub async fn get_object_by_hash(&self, hash: &str) -> Result {
+        self.client
+            .get_object_by_hash(hash)
+            .await?
+            .print(self.output);
 
         Ok(())
     }
@@ -170,6 +194,94 @@ impl Client {
         Ok(())
     }
 
+    pub async fn get_parts(&self) -> Result {
+        self.client.get_parts().await?.print(self.output);
+
+        Ok(())
+    }
+
+    pub async fn link_objects(
+        &self,
+        bucket: Uuid,
+        objects: &[String],
+    ) -> Result {
+        let mut linked = Vec::with_capacity(objects.len());
+
+        for object in objects {
+            let object = match Uuid::try_parse(object) {
+                Ok(id) => self.client.link_object(bucket, id).await?,
+                Err(_) => {
+                    self.client.link_object_by_hash(bucket, object).await?
+                }
+            };
+
+            linked.push(object);
+        }
+
+        linked.print(self.output);
+
+        Ok(())
+    }
+
+    /// Prints the objects in a bucket matching `query`.
+    ///
+    /// If `paged` is set, only a single page is printed, followed by the
+    /// cursor for the next page if there is one. Otherwise, each page is
+    /// printed as it is received.
+    pub async fn list_objects(
+        &self,
+        bucket: Uuid,
+        query: ObjectQuery,
+        paged: bool,
+    ) -> Result {
+        if paged {
+            let page = self.client.get_object_page(bucket, &query).await?;
+
+            page.objects.print(self.output);
+
+            if let Some(next) = page.next {
+                eprintln!("More objects available: --after={next}");
+            }
+
+            return Ok(());
+        }
+
+        let mut pages = pin!(self.client.get_object_pages(bucket, query));
+        let mut printer = PagePrinter::new(self.output);
+
+        while let Some(page) = pages.next().await {
+            printer.print(page?.objects);
+        }
+
+        printer.finish();
+
+        Ok(())
+    }
+
+    pub async fn presign_get(
+        &self,
+        bucket: Uuid,
+        object: Uuid,
+        expires: Option<Duration>,
+    ) -> Result {
+        let url = self.client.presign_get(bucket, object, expires).await?;
+        println!("{url}");
+
+        Ok(())
+    }
+
+    pub async fn presign_put(
+        &self,
+        bucket: Uuid,
+        key: &str,
+        expires: Option<Duration>,
+    ) -> Result {
+        let url = self.client.presign_put(bucket, key, expires).await?;
+        println!("{url}");
+
+        Ok(())
+    }
+
     pub async fn prune(&self, print_objects: bool) -> Result {
         let objects = self.client.prune().await?;
 
@@ -197,11 +309,43 @@ impl Client {
         Ok(())
     }
 
+    pub async fn put_key(
+        &self,
+        bucket: Uuid,
+        key: &str,
+        file: Option<PathBuf>,
+        options: &UploadOptions,
+    ) -> Result {
+        let object = match file {
+            Some(path) => {
+                let file = File::open(&path).await.map_err(|err| {
+                    format!("Failed to open file '{}': {err}", path.display())
+                })?;
+
+                self.client.put_key_with(bucket, key, file, options).await?
+            }
+            None => {
+                self.client
+                    .put_key_with(bucket, key, stdin(), options)
+                    .await?
+            }
+        };
+
+        object.print(self.output);
+
+        Ok(())
+    }
+
     pub async fn remove_bucket(&self, id: Uuid) -> Result {
         self.client.remove_bucket(&id).await?;
         Ok(())
     }
 
+    pub async fn remove_key(&self, bucket: Uuid, key: &str) -> Result {
+        self.client.remove_key(bucket, key).await?;
+        Ok(())
+    }
+
     pub async fn remove_objects(
         &self,
         bucket: Uuid,
@@ -237,25 +381,198 @@ impl Client {
         Ok(())
     }
 
-    pub async fn stream_stdin(&self, bucket: Uuid) -> Result {
+    pub async fn stream_stdin(
+        &self,
+        bucket: Uuid,
+        options: &UploadOptions,
+    ) -> Result {
         self.client
-            .add_object(bucket, stdin())
+            .add_object_with(bucket, stdin(), options)
             .await?
             .print(self.output);
 
         Ok(())
     }
 
-    pub async fn upload_file(&self, bucket: Uuid, file: PathBuf) -> Result {
+    pub async fn update_bucket(
+        &self,
+        id: &Uuid,
+        patch: &BucketPatch,
+    ) -> Result {
+        self.client
+            .update_bucket(id, patch)
+            .await?
+            .print(self.output);
+
+        Ok(())
+    }
+
+    /// Prints an object's metadata after applying any `changes`.
+    pub async fn user_metadata(
+        &self,
+        bucket: Uuid,
+        object: Uuid,
+        changes: MetadataPatch,
+    ) -> Result {
+        let metadata = if changes.is_empty() {
+            self.client.get_user_metadata(bucket, object).await?
+        } else {
+            self.client
+                .update_user_metadata(bucket, object, &changes)
+                .await?
+        };
+
+        metadata.print(self.output);
+
+        Ok(())
+    }
+
+    pub async fn upload_file(
+        &self,
+        bucket: Uuid,
+        file: PathBuf,
+        options: &UploadOptions,
+    ) -> Result {
         let file = File::open(&file).await.map_err(|err| {
             format!("Failed to open file '{}': {err}", file.display())
         })?;
 
         self.client
-            .add_object(bucket, file)
+            .add_object_with(bucket, file, options)
             .await?
             .print(self.output);
 
         Ok(())
     }
+
+    pub async fn upload_file_chunked(
+        &self,
+        bucket: Uuid,
+        path: PathBuf,
+        part: Option<Uuid>,
+        chunk_size: usize,
+        options: &UploadOptions,
+    ) -> Result {
+        let mut file = File::open(&path).await.map_err(|err| {
+            format!("Failed to open file '{}': {err}", path.display())
+        })?;
+
+        let mut upload = match part {
+            Some(id) => self.client.resume_upload(id).await?,
+            None => {
+                let upload = self.client.new_upload().await?;
+                eprintln!("Upload ID: {}", upload.id());
+                upload
+            }
+        };
+
+        let mut retries = 0;
+        let mut result = upload.upload_from(&mut file, chunk_size).await;
+
+        while let Err(err) = result {
+            if retries == UPLOAD_RETRIES {
+                return Err(format!(
+                    "Upload failed: {err}\n\
+                    Resume the upload with: --resume={}",
+                    upload.id()
+                )
+                .into());
+            }
+
+            retries += 1;
+            eprintln!(
+                "Upload interrupted: {err}; \
+                retrying ({retries}/{UPLOAD_RETRIES})"
+            );
+
+            sleep(UPLOAD_RETRY_DELAY).await;
+
+            // The server may have stored part of the failed request's data.
+            result = match upload.refresh().await {
+                Ok(_) => upload.upload_from(&mut file, chunk_size).await,
+                Err(err) => Err(err),
+            };
+        }
+
+        upload.commit(bucket, options).await?.print(self.output);
+
+        Ok(())
+    }
+}
+
+fn build_client(server: &Server) -> result::Result<http::Client, BoxError> {
+    let read = |path: &PathBuf| {
+        fs::read(path).map_err(|err| {
+            format!("failed to read '{}': {err}", path.display())
+        })
+    };
+
+    let mut builder = http::Client::builder(&server.url);
+
+    #[cfg(unix)]
+    if let Some(socket) = &server.socket {
+        builder = builder.unix_socket(socket);
+    }
+
+    if let Some(token) = &server.token {
+        builder = builder.token(token)?;
+    }
+
+    if let Some(path) = &server.ca_certificate {
+        builder = builder.ca_certificates(&read(path)?)?;
+    }
+
+    match (&server.certificate, &server.key) {
+        (Some(certificate), Some(key)) => {
+            builder = builder.identity(&read(certificate)?, &read(key)?)?;
+        }
+        (None, None) => (),
+        _ => {
+            return Err("a client certificate requires both 'certificate' \
+                and 'key' to be set"
+                .into())
+        }
+    }
+
+    Ok(builder.build()?)
+}
+
+async fn write_stream<R>(reader: R, destination: Option<PathBuf>) -> Result
+where
+    R: AsyncRead,
+{
+    let mut reader = pin!(reader);
+
+    match destination {
+        Some(path) => {
+            let mut file = File::options()
+                .create(true)
+                .write(true)
+                .truncate(true)
+                .open(&path)
+                .await
+                .map_err(|err| {
+                    format!(
+                        "Failed to open file for writing '{}': {err}",
+                        path.display()
+                    )
+                })?;
+
+            tokio::io::copy(&mut reader, &mut file)
+                .await
+                .map_err(|err| {
+                    format!(
+                        "Failed to stream object data to file '{}': {err}",
+                        path.display()
+                    )
+                })?;
+        }
+        None => {
+            tokio::io::copy(&mut reader, &mut stdout()).await.map_err(
+                |err| format!("Failed to stream object data to STDOUT: {err}"),
+            )?;
+        }
+    }
+
+    Ok(())
 }
@@ -9,6 +9,23 @@ use url::Url;
 #[derive(Debug, Deserialize, Serialize)]
 pub struct Server {
     pub url: Url,
+
+    /// Unix socket to send requests over instead of connecting to the URL's
+    /// host, for servers running on the same machine.
+    #[cfg(unix)]
+    pub socket: Option<PathBuf>,
+
+    /// API token sent with every request, if the server requires one.
+    pub token: Option<String>,
+
+    /// PEM file of CA certificates to trust in addition to the system's.
+    pub ca_certificate: Option<PathBuf>,
+
+    /// PEM file of a client certificate, for servers that verify clients.
+    pub certificate: Option<PathBuf>,
+
+    /// PEM file of the client certificate's PKCS #8 private key.
+    pub key: Option<PathBuf>,
 }
 
 #[derive(Debug, Default, Deserialize, Serialize)]
@@ -7,8 +7,17 @@ use conf::Config;
 use print::Output;
 
 use clap::{Args, Parser, Subcommand};
-use fstore::Uuid;
-use std::{path::PathBuf, process::ExitCode, result};
+use fstore::{
+    http::{self, UploadOptions},
+    ArchiveFormat, BucketPatch, DateTime, LabelSelector, MetadataPatch,
+    ObjectQuery, Uuid,
+};
+use std::{
+    path::{Path, PathBuf},
+    process::ExitCode,
+    result,
+    time::Duration,
+};
 
 #[derive(Debug, Parser)]
 #[command(name = "fstore", version, arg_required_else_help = true)]
@@ -59,6 +68,13 @@ enum Command {
     /// Get detailed information about the server
     About,
 
+    /// Discard uploads that have not been committed
+    Abort {
+        /// Upload IDs
+        #[arg(required = true)]
+        parts: Vec<Uuid>,
+    },
+
     /// Add an object to a bucket
     Add {
         /// Bucket ID
@@ -66,16 +82,94 @@ enum Command {
 
         /// File to upload (STDIN if missing)
         file: Option<PathBuf>,
+
+        #[arg(long, value_name = "SHA256")]
+        /// Expected SHA-256 hash of the object's contents
+        ///
+        /// The upload is rejected if the server computes a different hash
+        hash: Option<String>,
+
+        #[arg(
+            short,
+            long = "meta",
+            value_name = "NAME=VALUE",
+            value_parser = parse_metadata
+        )]
+        /// Store a metadata entry with the object
+        ///
+        /// May be specified multiple times
+        metadata: Vec<(String, String)>,
+
+        #[arg(long, value_name = "FILENAME")]
+        /// Name suggested to clients that download the object
+        ///
+        /// Defaults to the name of the uploaded file
+        name: Option<String>,
+
+        #[arg(
+            long,
+            value_name = "PART",
+            require_equals = true,
+            requires = "file"
+        )]
+        /// Upload the file in chunks so the upload can be resumed
+        ///
+        /// Without a value, a new upload is started and its ID is printed
+        /// to STDERR. Pass that ID to continue an interrupted upload.
+        resume: Option<Option<Uuid>>,
+
+        #[arg(
+            long,
+            value_name = "BYTES",
+            default_value_t = http::DEFAULT_CHUNK_SIZE,
+            requires = "resume"
+        )]
+        /// Number of bytes to send per request when resuming uploads
+        chunk_size: usize,
     },
 
     Bucket(BucketArgs),
 
     /// List all buckets
-    Buckets,
+    Buckets {
+        #[arg(short, long, value_name = "SELECTOR")]
+        /// Only list buckets with matching labels
+        ///
+        /// A comma-separated list of NAME=VALUE entries, which require a
+        /// label with the given value, and NAME entries, which only require
+        /// the label to exist
+        labels: Option<LabelSelector>,
+    },
 
     /// List object errors
     Errors,
 
+    /// Download a bucket's objects as an archive
+    ///
+    /// The archive contains a 'manifest.json' entry describing the objects,
+    /// followed by the data of each object, named by its ID and extension
+    Export {
+        /// Bucket UUID
+        bucket: Uuid,
+
+        /// Object UUIDs to export (all objects in the bucket if missing)
+        objects: Vec<Uuid>,
+
+        #[arg(short, long, default_value_t)]
+        /// Archive format: 'tar' or 'zip'
+        format: ArchiveFormat,
+
+        #[arg(short, long, value_name = "FILE")]
+        /// File to write the archive to (STDOUT if missing)
+        output: Option<PathBuf>,
+    },
+
+    /// Find an object by the SHA-256 hash of its contents
+    Find {
+        /// SHA-256 hash
+        hash: String,
+    },
+
     /// Stream an object's contents
     Get {
         /// Bucket UUID
@@ -88,6 +182,41 @@ enum Command {
         file: Option<PathBuf>,
     },
 
+    Key(KeyArgs),
+
+    /// Add objects already in the store to a bucket without uploading them
+    Link {
+        /// Bucket UUID
+        bucket: Uuid,
+
+        /// Object UUIDs or SHA-256 hashes
+        objects: Vec<String>,
+    },
+
+    /// View or edit an object's metadata
+    ///
+    /// Without options, the object's metadata is printed
+    Meta {
+        /// Bucket UUID
+        bucket: Uuid,
+
+        /// Object UUID
+        object: Uuid,
+
+        #[arg(short, long, value_name = "NAME=VALUE", value_parser = parse_metadata)]
+        /// Set a metadata entry
+        set: Vec<(String, String)>,
+
+        #[arg(short, long, value_name = "NAME")]
+        /// Remove a metadata entry
+        unset: Vec<String>,
+    },
+
+    /// List uploads that have not been committed
+    Parts,
+
+    Presign(PresignArgs),
+
     /// Delete objects not referenced by a bucket
     Prune {
         /// Print the objects that were deleted
@@ -111,9 +240,73 @@ enum Command {
 
         /// Object UUIDs
         object: Option<Vec<Uuid>>,
+
+        #[command(flatten)]
+        filter: ObjectFilter,
     },
 }
 
+#[derive(Debug, Args)]
+#[group(requires = "bucket", conflicts_with = "object")]
+/// Options for listing the objects in a bucket
+struct ObjectFilter {
+    #[arg(long, value_name = "TYPE[/SUBTYPE]")]
+    /// Only list objects with this media type
+    media_type: Option<String>,
+
+    #[arg(long, value_name = "BYTES")]
+    /// Only list objects of at least this size
+    min_size: Option<u64>,
+
+    #[arg(long, value_name = "BYTES")]
+    /// Only list objects of at most this size
+    max_size: Option<u64>,
+
+    #[arg(long, value_name = "DATE")]
+    /// Only list objects added to the bucket at or after this RFC 3339 date
+    added_after: Option<DateTime>,
+
+    #[arg(long, value_name = "DATE")]
+    /// Only list objects added to the bucket before this RFC 3339 date
+    added_before: Option<DateTime>,
+
+    #[arg(short, long)]
+    /// List at most this many objects
+    ///
+    /// If more objects match, a cursor for listing the next page is
+    /// printed to STDERR
+    limit: Option<u32>,
+
+    #[arg(long, value_name = "CURSOR")]
+    /// Continue a listing from a cursor printed by a previous command
+    after: Option<String>,
+}
+
+impl From<ObjectFilter> for ObjectQuery {
+    fn from(value: ObjectFilter) -> Self {
+        let (r#type, subtype) = match value.media_type {
+            Some(media_type) => match media_type.split_once('/') {
+                Some((ty, subtype)) => {
+                    (Some(ty.to_owned()), Some(subtype.to_owned()))
+                }
+                None => (Some(media_type), None),
+            },
+            None => (None, None),
+        };
+
+        Self {
+            after: value.after,
+            limit: value.limit,
+            r#type,
+            subtype,
+            min_size: value.min_size,
+            max_size: value.max_size,
+            added_after: value.added_after,
+            added_before: value.added_before,
+        }
+    }
+}
+
 #[derive(Debug, Args)]
 #[command(args_conflicts_with_subcommands = true, flatten_help = true)]
 /// Get information about a bucket
@@ -155,6 +348,24 @@ enum Bucket {
         name: String,
     },
 
+    /// Change a bucket's description or labels
+    Edit {
+        /// Bucket UUID
+        id: Uuid,
+
+        #[arg(short, long)]
+        /// Set the bucket's description (an empty string removes it)
+        description: Option<String>,
+
+        #[arg(short, long, value_name = "NAME=VALUE", value_parser = parse_metadata)]
+        /// Set a label
+        set: Vec<(String, String)>,
+
+        #[arg(short, long, value_name = "NAME")]
+        /// Remove a label
+        unset: Vec<String>,
+    },
+
     /// Retrieve information about a bucket
     Get(BucketGetArg),
 
@@ -174,6 +385,144 @@ enum Bucket {
     },
 }
 
+#[derive(Debug, Args)]
+/// Manage objects by their keys within a bucket
+struct KeyArgs {
+    #[command(subcommand)]
+    command: Key,
+}
+
+#[derive(Debug, Subcommand)]
+enum Key {
+    /// Stream the contents of the object stored under a key
+    Get {
+        /// Bucket UUID
+        bucket: Uuid,
+
+        /// Object key
+        key: String,
+
+        /// File to stream data to (STDOUT if missing)
+        file: Option<PathBuf>,
+    },
+
+    /// List keys in a bucket
+    Ls {
+        /// Bucket UUID
+        bucket: Uuid,
+
+        /// Only list keys beginning with this prefix
+        #[arg(default_value = "")]
+        prefix: String,
+
+        #[arg(short, long)]
+        /// Group keys containing this string after the prefix
+        ///
+        /// Keys that share everything up to and including the delimiter
+        /// are listed once as a common prefix
+        delimiter: Option<String>,
+    },
+
+    /// Upload an object and store it under a key
+    ///
+    /// Any object previously stored under the key is removed from the bucket
+    Put {
+        /// Bucket UUID
+        bucket: Uuid,
+
+        /// Object key
+        key: String,
+
+        /// File to upload (STDIN if missing)
+        file: Option<PathBuf>,
+
+        #[arg(long, value_name = "SHA256")]
+        /// Expected SHA-256 hash of the object's contents
+        ///
+        /// The upload is rejected if the server computes a different hash
+        hash: Option<String>,
+
+        #[arg(
+            short,
+            long = "meta",
+            value_name = "NAME=VALUE",
+            value_parser = parse_metadata
+        )]
+        /// Store a metadata entry with the object
+        ///
+        /// May be specified multiple times
+        metadata: Vec<(String, String)>,
+
+        #[arg(long, value_name = "FILENAME")]
+        /// Name suggested to clients that download the object
+        ///
+        /// Defaults to the name of the uploaded file
+        name: Option<String>,
+    },
+
+    /// Remove the object stored under a key from a bucket
+    Rm {
+        /// Bucket UUID
+        bucket: Uuid,
+
+        /// Object key
+        key: String,
+    },
+}
+
+#[derive(Debug, Args)]
+/// Create temporary URLs that do not require an API token
+struct PresignArgs {
+    #[arg(short, long, value_name = "SECONDS", global = true)]
+    /// Seconds until the URL expires (one hour if missing)
+    expires: Option<u64>,
+
+    #[command(subcommand)]
+    command: Presign,
+}
+
+#[derive(Debug, Subcommand)]
+enum Presign {
+    /// Create a URL that downloads an object's contents
+    Get {
+        /// Bucket UUID
+        bucket: Uuid,
+
+        /// Object UUID
+        object: Uuid,
+    },
+
+    /// Create a URL that uploads an object under a key with a PUT request
+    Put {
+        /// Bucket UUID
+        bucket: Uuid,
+
+        /// Object key
+        key: String,
+    },
+}
+
+fn parse_metadata(entry: &str) -> result::Result<(String, String), String> {
+    match entry.split_once('=') {
+        Some((name, value)) if !name.is_empty() => {
+            Ok((name.to_owned(), value.to_owned()))
+        }
+        _ => Err(format!("expected NAME=VALUE, found '{entry}'")),
+    }
+}
+
+/// Returns the filename to store with an upload: `name` if given, or else
+/// the name of the uploaded file.
+fn upload_filename(
+    name: Option<String>,
+    file: Option<&Path>,
+) -> Option<String> {
+    name.or_else(|| {
+        file.and_then(Path::file_name)
+            .map(|name| name.to_string_lossy().into_owned())
+    })
+}
+
 fn main() -> ExitCode {
     let args = Cli::parse();
     let config = match args.config() {
@@ -197,13 +546,19 @@ fn main() -> ExitCode {
         }
     };
 
-    let client = Client::new(
+    let client = match Client::new(
         server,
         Output {
             human_readable: args.human_readable,
             json: args.json,
         },
-    );
+    ) {
+        Ok(client) => client,
+        Err(err) => {
+            eprintln!("{err}");
+            return ExitCode::FAILURE;
+        }
+    };
 
     match run(args.command, client) {
         Ok(()) => ExitCode::SUCCESS,
@@ -227,37 +582,155 @@ fn run(command: Command, client: Client) -> Result {
 async fn run_command(command: Command, client: Client) -> Result {
     match command {
         Command::About => client.about().await,
-        Command::Add { bucket, file } => match file {
-            Some(file) => client.upload_file(bucket, file).await,
-            None => client.stream_stdin(bucket).await,
-        },
+        Command::Abort { parts } => client.abort_uploads(parts).await,
+        Command::Add {
+            bucket,
+            file,
+            hash,
+            metadata,
+            name,
+            resume,
+            chunk_size,
+        } => {
+            let options = UploadOptions {
+                hash,
+                metadata: metadata.into_iter().collect(),
+                filename: upload_filename(name, file.as_deref()),
+                size: None,
+            };
+
+            match (file, resume) {
+                (Some(file), Some(part)) => {
+                    client
+                        .upload_file_chunked(
+                            bucket, file, part, chunk_size, &options,
+                        )
+                        .await
+                }
+                (Some(file), None) => {
+                    client.upload_file(bucket, file, &options).await
+                }
+                (None, _) => client.stream_stdin(bucket, &options).await,
+            }
+        }
         Command::Bucket(args) => match args.command() {
             Bucket::Add { name } => client.add_bucket(name).await,
             Bucket::Clone { original, name } => {
                 client.clone_bucket(original, name).await
             }
+            Bucket::Edit {
+                id,
+                description,
+                set,
+                unset,
+            } => {
+                let patch = BucketPatch {
+                    description,
+                    labels: set
+                        .into_iter()
+                        .map(|(name, value)| (name, Some(value)))
+                        .chain(unset.into_iter().map(|name| (name, None)))
+                        .collect(),
+                };
+
+                client.update_bucket(&id, &patch).await
+            }
             Bucket::Get(BucketGetArg { name }) => client.get_bucket(name).await,
             Bucket::Rm { id } => client.remove_bucket(id).await,
             Bucket::Rename { id, name } => {
                 client.rename_bucket(&id, &name).await
             }
         },
-        Command::Buckets => client.get_buckets().await,
+        Command::Buckets { labels } => {
+            client.get_buckets(&labels.unwrap_or_default()).await
+        }
         Command::Errors => client.get_object_errors().await,
+        Command::Export {
+            bucket,
+            objects,
+            format,
+            output,
+        } => client.export(bucket, &objects, format, output).await,
+        Command::Find { hash } => client.get_object_by_hash(&hash).await,
         Command::Get {
             bucket,
             object,
             file,
         } => client.get_object(bucket, object, file).await,
+        Command::Key(KeyArgs { command }) => match command {
+            Key::Get { bucket, key, file } => {
+                client.get_key(bucket, &key, file).await
+            }
+            Key::Ls {
+                bucket,
+                prefix,
+                delimiter,
+            } => client.get_keys(bucket, &prefix, delimiter.as_deref()).await,
+            Key::Put {
+                bucket,
+                key,
+                file,
+                hash,
+                metadata,
+                name,
+            } => {
+                let options = UploadOptions {
+                    hash,
+                    metadata: metadata.into_iter().collect(),
+                    filename: upload_filename(name, file.as_deref()),
+                    size: None,
+                };
+
+                client.put_key(bucket, &key, file, &options).await
+            }
+            Key::Rm { bucket, key } => client.remove_key(bucket, &key).await,
+        },
+        Command::Link { bucket, objects } => {
+            client.link_objects(bucket, &objects).await
+        }
+        Command::Meta {
+            bucket,
+            object,
+            set,
+            unset,
+        } => {
+            let changes: MetadataPatch = set
+                .into_iter()
+                .map(|(name, value)| (name, Some(value)))
+                .chain(unset.into_iter().map(|name| (name, None)))
+                .collect();
+
+            client.user_metadata(bucket, object, changes).await
+        }
+        Command::Parts => client.get_parts().await,
+        Command::Presign(PresignArgs { expires, command }) => {
+            let expires = expires.map(Duration::from_secs);
+
+            match command {
+                Presign::Get { bucket, object } => {
+                    client.presign_get(bucket, object, expires).await
+                }
+                Presign::Put { bucket, key } => {
+                    client.presign_put(bucket, &key, expires).await
+                }
+            }
+        }
         Command::Prune { verbose } => client.prune(verbose).await,
         Command::Rm { bucket, objects } => {
             client.remove_objects(bucket, objects).await
         }
-        Command::Stat { bucket, object } => match (bucket, object) {
+        Command::Stat {
+            bucket,
+            object,
+            filter,
+        } => match (bucket, object) {
             (Some(bucket), Some(object)) => {
                 client.get_objects(bucket, &object).await
             }
-            (Some(bucket), None) => client.get_all_objects(bucket).await,
+            (Some(bucket), None) => {
+                let paged = filter.limit.is_some();
+                client.list_objects(bucket, filter.into(), paged).await
+            }
             _ => client.status().await,
         },
     }
@@ -1,10 +1,13 @@
 use chrono_humanize::{Accuracy, HumanTime, Tense};
-use fstore::{Bucket, DateTime, Object, StoreTotals};
+use fstore::{Bucket, DateTime, KeyList, Metadata, Object, Part, StoreTotals};
 use log::debug;
 use num_format::{SystemLocale, ToFormattedString};
 use serde::Serialize;
 use serde_json as json;
-use std::io::{stdout, IsTerminal};
+use std::{
+    io::{stdout, IsTerminal, Write},
+    marker::PhantomData,
+};
 use tabled::{
     builder::Builder,
     settings::{object::Columns, Alignment, Padding, Reverse, Rotate, Style},
@@ -74,15 +77,33 @@ impl Tabulate for Bucket {
     fn tabulate(self) -> Table {
         let mut builder = Builder::default();
 
-        builder.push_record(["ID", "Name", "Created", "Objects", "Storage"]);
-
-        builder.push_record([
+        let mut header = vec!["ID", "Name", "Created", "Objects", "Storage"];
+        let mut record = vec![
             self.id.to_string(),
             self.name,
             self.created.long_date(),
             self.object_count.format(),
             self.space_used.disk_usage_string(),
-        ]);
+        ];
+
+        if let Some(description) = self.description {
+            header.insert(2, "Description");
+            record.insert(2, description);
+        }
+
+        if !self.labels.is_empty() {
+            header.push("Labels");
+            record.push(
+                self.labels
+                    .iter()
+                    .map(|(name, value)| format!("{name}: {value}"))
+                    .collect::<Vec<_>>()
+                    .join("\n"),
+            );
+        }
+
+        builder.push_record(header);
+        builder.push_record(record);
 
         let mut table = builder.build();
 
@@ -129,21 +150,94 @@ impl Tabulate for Vec<Bucket> {
     }
 }
 
+impl Tabulate for KeyList {
+    fn tabulate(self) -> Table {
+        let mut builder = Builder::default();
+
+        builder.push_record(["Key", "ID", "Size", "Type", "Added"]);
+
+        for prefix in self.prefixes {
+            builder.push_record([prefix]);
+        }
+
+        for object in self.objects {
+            let media_type = object.media_type();
+
+            builder.push_record([
+                object.key.unwrap_or_default(),
+                object.id.to_string(),
+                bytesize::to_string(object.size, true),
+                media_type,
+                object.added.to_string(),
+            ]);
+        }
+
+        let mut table = builder.build();
+
+        table
+            .modify(Columns::single(2), Alignment::right())
+            .with(Style::modern_rounded());
+
+        table
+    }
+}
+
+impl Tabulate for Metadata {
+    fn tabulate(self) -> Table {
+        let mut builder = Builder::default();
+
+        builder.push_record(["Name", "Value"]);
+
+        for (name, value) in self {
+            builder.push_record([name, value]);
+        }
+
+        let mut table = builder.build();
+
+        table.with(Style::modern_rounded());
+
+        table
+    }
+}
+
 impl Tabulate for Object {
     fn tabulate(self) -> Table {
         let media_type = self.media_type();
 
         let mut builder = Builder::default();
 
-        builder.push_record(["ID", "SHA 256", "Size", "Type", "Added"]);
-
-        builder.push_record([
+        let mut header = vec!["ID", "SHA 256", "Size", "Type", "Added"];
+        let mut record = vec![
             self.id.to_string(),
             self.hash,
             self.size.disk_usage_string(),
             media_type,
             self.added.long_date(),
-        ]);
+        ];
+
+        if let Some(key) = self.key {
+            header.insert(1, "Key");
+            record.insert(1, key);
+        }
+
+        if let Some(filename) = self.filename {
+            header.push("Filename");
+            record.push(filename);
+        }
+
+        if !self.metadata.is_empty() {
+            header.push("Metadata");
+            record.push(
+                self.metadata
+                    .iter()
+                    .map(|(name, value)| format!("{name}: {value}"))
+                    .collect::<Vec<_>>()
+                    .join("\n"),
+            );
+        }
+
+        builder.push_record(header);
+        builder.push_record(record);
 
         let mut table = builder.build();
 
@@ -194,6 +288,30 @@ impl Tabulate for Vec<Object> {
     }
 }
 
+impl Tabulate for Vec<Part> {
+    fn tabulate(self) -> Table {
+        let mut builder = Builder::default();
+
+        builder.push_record(["ID", "Size", "Last Modified"]);
+
+        for part in self {
+            builder.push_record([
+                part.id.to_string(),
+                bytesize::to_string(part.size, true),
+                part.modified.to_string(),
+            ]);
+        }
+
+        let mut table = builder.build();
+
+        table
+            .modify(Columns::single(1), Alignment::right())
+            .with(Style::modern_rounded());
+
+        table
+    }
+}
+
 impl Tabulate for StoreTotals {
     fn tabulate(self) -> Table {
         let mut builder = Builder::default();
@@ -251,3 +369,71 @@ where
         }
     }
 }
+
+/// Prints a list as its pages are received, rather than collecting every
+/// page first. JSON output is written as a single array.
+pub struct PagePrinter<T> {
+    output: Output,
+    json: bool,
+    printed: usize,
+    item: PhantomData<T>,
+}
+
+impl<T> PagePrinter<T>
+where
+    T: Serialize,
+    Vec<T>: Tabulate,
+{
+    pub fn new(output: Output) -> Self {
+        Self {
+            output,
+            json: output.json
+                || !(output.human_readable || stdout().is_terminal()),
+            printed: 0,
+            item: PhantomData,
+        }
+    }
+
+    pub fn print(&mut self, items: Vec<T>) {
+        if items.is_empty() {
+            return;
+        }
+
+        if self.json {
+            let mut stdout = stdout().lock();
+
+            for item in &items {
+                let separator = if self.printed == 0 { "[" } else { "," };
+                self.printed += 1;
+
+                write!(stdout, "{separator}{}", json::to_string(item).unwrap())
+                    .unwrap();
+            }
+
+            stdout.flush().unwrap();
+        } else {
+            self.printed += items.len();
+        }
+
+        self.print_table(items);
+    }
+
+    /// Ends the output, printing an empty list if no items were received.
+    pub fn finish(self) {
+        if self.json {
+            println!("{}", if self.printed == 0 { "[]" } else { "]" });
+        }
+
+        if self.printed == 0 {
+            self.print_table(Vec::new());
+        }
+    }
+
+    fn print_table(&self, items: Vec<T>) {
+        if !self.json {
+            println!("{}", items.tabulate());
+        } else if self.output.human_readable {
+            eprintln!("{}", items.tabulate());
+        }
+    }
+}
@@ -10,6 +10,7 @@ use sqlx::{
     postgres::{
         PgConnectOptions as ConnectOptions, PgPoolOptions as PoolOptions,
     },
+    types::Json,
     ConnectOptions as _,
 };
 use sqlx_helper_macros::{database, transaction};
@@ -23,37 +24,122 @@ database! {
         size: i64,
         ty: &str,
         subtype: &str,
+        key: Option<&str>,
+        metadata: Json<&fstore::Metadata>,
+        filename: Option<&str>,
     ) -> Object;
 
     clone_bucket(original: Uuid, name: &str) -> Bucket;
 
     create_bucket(name: &str) -> Bucket;
 
+    create_token(
+        name: &str,
+        secret_hash: &str,
+        scope: &str,
+        buckets: Option<&[Uuid]>,
+    ) -> Token;
+
     fetch_bucket(name: &str) -> Bucket;
 
-    fetch_buckets_all() -> Vec<Bucket>;
+    fetch_bucket_by_id(bucket_id: &Uuid) -> Option<Bucket>;
+
+    fetch_buckets_all(
+        labels: Json<&fstore::Labels>,
+        label_names: &[String],
+    ) -> Vec<Bucket>;
 
     fetch_store_totals() -> StoreTotals;
 
+    get_bucket_contents(
+        bucket_id: Uuid,
+        objects: Option<&[Uuid]>,
+    ) -> Vec<Object>;
+
+    get_bucket_object(bucket_id: Uuid, object_id: Uuid) -> Option<Object>;
+
     get_bucket_objects(bucket_id: Uuid) -> Vec<Object>;
 
+    get_bucket_objects_page(
+        bucket_id: Uuid,
+        after_added: Option<Timestamp>,
+        after_id: Option<Uuid>,
+        limit: i64,
+        ty: Option<&str>,
+        subtype: Option<&str>,
+        min_size: Option<i64>,
+        max_size: Option<i64>,
+        added_after: Option<Timestamp>,
+        added_before: Option<Timestamp>,
+    ) -> Vec<Object>;
+
+    get_errored_objects() -> Vec<Object>;
+
     get_errors() -> Vec<ObjectError>;
 
+    get_key_prefixes(
+        bucket_id: Uuid,
+        prefix: &str,
+        delimiter: &str,
+    ) -> Vec<String>;
+
+    get_keyed_objects(
+        bucket_id: Uuid,
+        prefix: &str,
+        delimiter: Option<&str>,
+    ) -> Vec<Object>;
+
+    get_missing_objects(objects: &[Uuid]) -> Vec<Uuid>;
+
     get_objects(bucket_id: Uuid, objects: &[Uuid]) -> Vec<Object>;
 
+    get_object_by_hash(hash: &str) -> Option<Object>;
+
+    get_object_by_key(bucket_id: Uuid, key: &str) -> Option<Object>;
+
     get_object_count(before: Timestamp) -> i64;
 
+    get_token(secret_hash: &str) -> Option<Token>;
+
+    get_tokens() -> Vec<Token>;
+
+    link_object(bucket_id: &Uuid, object_id: &Uuid) -> Option<Object>;
+
+    link_object_by_hash(bucket_id: &Uuid, hash: &str) -> Option<Object>;
+
+    put_object_key(bucket_id: Uuid, key: &str, object_id: Uuid) -> Object;
+
+    stream_bucket_objects(bucket_id: Uuid) -> Stream<Object>;
+
     stream_objects(before: Timestamp) -> Stream<Object>;
 
     remove_bucket(bucket_id: &Uuid);
 
     remove_object(bucket_id: &Uuid, object_id: &Uuid) -> Option<Object>;
 
+    remove_object_by_key(bucket_id: Uuid, key: &str) -> Option<Object>;
+
     remove_objects(bucket_id: &Uuid, objects: &[Uuid]) -> RemoveResult;
 
+    remove_token(token_id: &Uuid) -> Option<Token>;
+
     rename_bucket(bucket_id: &Uuid, name: &str);
 
+    update_bucket(
+        bucket_id: &Uuid,
+        description: Option<&str>,
+        set: Json<&fstore::Labels>,
+        remove: &[String],
+    ) -> Option<Bucket>;
+
     update_object_errors(records: &[ObjectError]);
+
+    update_object_metadata(
+        bucket_id: Uuid,
+        object_id: Uuid,
+        set: Json<&fstore::Metadata>,
+        remove: &[String],
+    ) -> Option<Object>;
 }
 
 transaction! {
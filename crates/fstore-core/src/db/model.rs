@@ -6,6 +6,7 @@ use sqlx::{
     postgres::{
         types::PgRecordEncoder, PgArgumentBuffer, PgHasArrayType, PgTypeInfo,
     },
+    types::Json,
     Encode, FromRow, Postgres, Type,
 };
 use uuid::Uuid;
@@ -19,6 +20,8 @@ pub struct Bucket {
     pub date_created: Timestamp,
     pub object_count: i64,
     pub space_used: i64,
+    pub description: Option<String>,
+    pub labels: Json<fstore::Labels>,
 }
 
 impl From<Bucket> for fstore::Bucket {
@@ -29,6 +32,8 @@ impl From<Bucket> for fstore::Bucket {
             created: value.date_created,
             object_count: value.object_count.try_into().unwrap(),
             space_used: value.space_used.try_into().unwrap(),
+            description: value.description,
+            labels: value.labels.0,
         }
     }
 }
@@ -41,6 +46,12 @@ pub struct Object {
     pub r#type: String,
     pub subtype: String,
     pub date_added: Timestamp,
+    #[sqlx(default)]
+    pub key: Option<String>,
+    #[sqlx(default)]
+    pub metadata: Option<Json<fstore::Metadata>>,
+    #[sqlx(default)]
+    pub filename: Option<String>,
 }
 
 impl From<Object> for fstore::Object {
@@ -56,6 +67,12 @@ impl From<Object> for fstore::Object {
             subtype: value.subtype,
             extension,
             added: value.date_added,
+            key: value.key,
+            metadata: value
+                .metadata
+                .map(|metadata| metadata.0)
+                .unwrap_or_default(),
+            filename: value.filename,
         }
     }
 }
@@ -134,3 +151,24 @@ impl From<ObjectError> for fstore::ObjectError {
         }
     }
 }
+
+#[derive(Debug, FromRow)]
+pub struct Token {
+    pub token_id: Uuid,
+    pub name: String,
+    pub scope: String,
+    pub buckets: Option<Vec<Uuid>>,
+    pub date_created: Timestamp,
+}
+
+impl From<Token> for crate::Token {
+    fn from(value: Token) -> Self {
+        crate::Token {
+            id: value.token_id,
+            name: value.name,
+            scope: value.scope.parse().unwrap(),
+            buckets: value.buckets,
+            created: value.date_created,
+        }
+    }
+}
@@ -6,9 +6,30 @@ pub enum Error {
     #[error("This object is being written to by another request")]
     WriteLock,
 
+    #[error("hash mismatch: expected {expected}, computed {actual}")]
+    HashMismatch { expected: String, actual: String },
+
     #[error("{0}")]
     Internal(String),
 
+    #[error("invalid cursor: {0}")]
+    InvalidCursor(&'static str),
+
+    #[error("invalid filename: {0}")]
+    InvalidFilename(&'static str),
+
+    #[error("invalid label: {0}")]
+    InvalidLabel(&'static str),
+
+    #[error("invalid key: {0}")]
+    InvalidKey(&'static str),
+
+    #[error("invalid metadata: {0}")]
+    InvalidMetadata(&'static str),
+
+    #[error("{0}")]
+    InvalidPart(String),
+
     #[error("task already in progress")]
     InProgress,
 
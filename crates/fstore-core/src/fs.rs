@@ -2,21 +2,27 @@ mod file_type;
 mod hash;
 mod part;
 mod rm;
+mod scan;
 
-pub use part::Part;
+pub use part::{Part, RangedPart};
+pub use scan::{FileInfo, Orphan, Scan};
 pub use tokio::fs::File;
 
 use file_type::{mime_type, MimeType};
-use part::PartLockSet;
+use part::{LockedPart, PartLockSet};
 
 use crate::error::{Error, Result};
 
+use chrono::Local;
 use log::debug;
 use std::{
     fs,
+    io::ErrorKind,
+    ops::Range,
     os::unix::fs::PermissionsExt,
     path::{Path, PathBuf},
     result,
+    time::SystemTime,
 };
 use uuid::Uuid;
 
@@ -25,6 +31,7 @@ const ID_SLICES: usize = 2;
 
 const OBJECTS_DIR: &str = "objects";
 const PARTS_DIR: &str = "parts";
+const QUARANTINE_DIR: &str = "quarantine";
 
 const OBJECT_PERMISSIONS: u32 = 0o640;
 
@@ -45,6 +52,16 @@ async fn check(path: &Path, hash: &str) -> result::Result<(), String> {
     }
 }
 
+fn check_size(actual: u64, expected: u64) -> Result<()> {
+    if actual != expected {
+        return Err(Error::InvalidPart(format!(
+            "Part has {actual} bytes, expected {expected}"
+        )));
+    }
+
+    Ok(())
+}
+
 fn create_directories(file: &Path) -> Result<()> {
     let parent = file.parent().ok_or_else(|| {
         Error::Internal(format!(
@@ -98,6 +115,7 @@ pub struct Object {
 pub struct Filesystem {
     objects: PathBuf,
     parts: PathBuf,
+    quarantine: PathBuf,
     locked_parts: PartLockSet,
 }
 
@@ -106,10 +124,19 @@ impl Filesystem {
         Self {
             objects: home.join(OBJECTS_DIR),
             parts: home.join(PARTS_DIR),
+            quarantine: home.join(QUARANTINE_DIR),
             locked_parts: PartLockSet::new(),
         }
     }
 
+    /// Lists the object files stored in an archive.
+    pub async fn archived_objects(
+        &self,
+        archive: &Path,
+    ) -> Result<Vec<FileInfo>> {
+        scan::files(&archive.join(OBJECTS_DIR)).await
+    }
+
     pub async fn check(
         &self,
         object_id: &Uuid,
@@ -119,9 +146,78 @@ impl Filesystem {
         check(&path, hash).await
     }
 
-    pub async fn commit(&self, part_id: &Uuid) -> Result<Object> {
-        let _lock = self.locked_parts.lock(part_id);
+    /// Checks whether restoring an object from an archive would replace the
+    /// home copy, without changing anything. The archived copy is read in
+    /// full; the home copy needs restoring if it is missing or differs.
+    pub async fn check_archived(
+        &self,
+        object_id: &Uuid,
+        archive: &Path,
+    ) -> result::Result<bool, String> {
+        let source = path_for_id(&archive.join(OBJECTS_DIR), object_id);
+
+        let hash = hash::sha256sum(&source).await.map_err(|err| {
+            format!("archived copy '{}' is unreadable: {err}", source.display())
+        })?;
+
+        Ok(check(&self.object_path(object_id), &hash).await.is_err())
+    }
+
+    pub async fn commit(
+        &self,
+        part_id: &Uuid,
+        expected_hash: Option<&str>,
+        expected_size: Option<u64>,
+    ) -> Result<Object> {
+        let _lock = self.locked_parts.lock(part_id)?;
+        let part = self.part_path(part_id);
+
+        if !part.exists() {
+            return Err(Error::NotFound("part"));
+        }
+
+        let size = self.part_info(part_id).await?.size;
+
+        let hash = match part::load_ranges(&part).await? {
+            Some(ranges) => {
+                // The file size only reflects the highest offset written, so
+                // a missing final chunk can only be detected with the size
+                // the client expects.
+                let expected = expected_size.ok_or_else(|| {
+                    Error::InvalidPart(
+                        "The expected size is required to commit a part \
+                        written at offsets"
+                            .into(),
+                    )
+                })?;
+
+                part::check_complete(&ranges, expected)?;
+                check_size(size, expected)?;
+                hash::sha256sum(&part).await?
+            }
+            None => {
+                if let Some(expected) = expected_size {
+                    check_size(size, expected)?;
+                }
+
+                hash::resume(&part).await?.finalize(&part)?
+            }
+        };
+
+        if let Some(expected) = expected_hash {
+            if !expected.eq_ignore_ascii_case(&hash) {
+                self.discard_part(part_id).await?;
+
+                return Err(Error::HashMismatch {
+                    expected: expected.to_ascii_lowercase(),
+                    actual: hash,
+                });
+            }
+        }
+
         let object = self.move_part(part_id)?;
+        hash::remove_state(&part)?;
+        part::remove_ranges(&part)?;
 
         let metadata = object.metadata().map_err(|err| {
             Error::Internal(format!(
@@ -135,7 +231,7 @@ impl Filesystem {
 
         Ok(Object {
             id: *part_id,
-            hash: hash::sha256sum(&object).await?,
+            hash,
             size: metadata.len(),
             r#type,
             subtype,
@@ -177,6 +273,77 @@ impl Filesystem {
         Ok(())
     }
 
+    /// Copies an object file from an archive into the home directory if the
+    /// home copy is missing or does not match `hash`.
+    ///
+    /// Returns whether the file was restored.
+    pub async fn restore(
+        &self,
+        object_id: &Uuid,
+        archive: &Path,
+        hash: &str,
+    ) -> result::Result<bool, String> {
+        let destination = self.object_path(object_id);
+
+        match check(&destination, hash).await {
+            Ok(()) => return Ok(false),
+            Err(err) => debug!(
+                "Restoring object ({object_id}) to '{}': {err}",
+                destination.display()
+            ),
+        }
+
+        let objects = archive.join(OBJECTS_DIR);
+        let source = path_for_id(&objects, object_id);
+
+        check(&source, hash)
+            .await
+            .map_err(|err| format!("archived copy is invalid: {err}"))?;
+
+        create_directories(&destination)
+            .map_err(|err| format!("failed to restore object file: {err}"))?;
+
+        tokio::fs::copy(&source, &destination)
+            .await
+            .map_err(|err| {
+                format!(
+                    "failed to copy object file from '{}' to '{}': {err}",
+                    source.display(),
+                    destination.display()
+                )
+            })?;
+
+        check(&destination, hash)
+            .await
+            .map_err(|err| format!("restored copy is invalid: {err}"))?;
+
+        Ok(true)
+    }
+
+    async fn discard_part(&self, part_id: &Uuid) -> Result<()> {
+        let part = self.part_path(part_id);
+
+        hash::remove_state(&part)?;
+        part::remove_ranges(&part)?;
+        rm::remove_files(vec![part]).await
+    }
+
+    /// Returns the ID of the part that `path` holds hash state or ranges
+    /// for, if it is such a file.
+    fn sidecar_id(&self, path: &Path) -> Option<Uuid> {
+        let extension = path.extension()?.to_str()?;
+
+        if extension != hash::STATE_EXTENSION
+            && extension != part::RANGES_EXTENSION
+        {
+            return None;
+        }
+
+        let id = Uuid::try_parse(path.file_stem()?.to_str()?).ok()?;
+
+        (self.part_path(&id).with_extension(extension) == path).then_some(id)
+    }
+
     fn move_part(&self, part_id: &Uuid) -> Result<PathBuf> {
         let part = self.part_path(part_id);
         let object = self.object_path(part_id);
@@ -210,19 +377,178 @@ impl Filesystem {
         path_for_id(&self.objects, id)
     }
 
+    /// Lists the object files whose inodes last changed before `before`.
+    pub async fn object_files(
+        &self,
+        before: SystemTime,
+    ) -> Result<Vec<FileInfo>> {
+        let mut files = scan::files(&self.objects).await?;
+        files.retain(|file| file.changed < before);
+        Ok(files)
+    }
+
+    /// Returns an orphan entry for an object file with no database record.
+    pub fn orphan(&self, id: &Uuid) -> Orphan {
+        Orphan {
+            path: self.object_path(id),
+            reason: "no database record",
+        }
+    }
+
     pub async fn part(&self, id: &Uuid) -> Result<Part> {
         Part::open(id, self.part_path(id), &self.locked_parts).await
     }
 
+    pub async fn part_info(&self, id: &Uuid) -> Result<FileInfo> {
+        let path = self.part_path(id);
+
+        match tokio::fs::metadata(&path).await {
+            Ok(metadata) => Ok(FileInfo::new(*id, &metadata)),
+            Err(err) if err.kind() == ErrorKind::NotFound => {
+                Err(Error::NotFound("part"))
+            }
+            Err(err) => Err(Error::Internal(format!(
+                "Failed to fetch metadata for part file '{}': {err}",
+                path.display()
+            ))),
+        }
+    }
+
+    /// Returns the byte ranges that have been written to a part.
+    pub async fn part_ranges(&self, id: &Uuid) -> Result<Vec<Range<u64>>> {
+        let path = self.part_path(id);
+
+        match part::load_ranges(&path).await? {
+            Some(ranges) => Ok(ranges),
+            None => {
+                let size = self.part_info(id).await?.size;
+                Ok((size > 0).then_some(0..size).into_iter().collect())
+            }
+        }
+    }
+
     fn part_path(&self, id: &Uuid) -> PathBuf {
         path_for_id(&self.parts, id)
     }
 
+    pub async fn parts(&self) -> Result<Vec<FileInfo>> {
+        scan::files(&self.parts).await
+    }
+
+    /// Moves orphaned entries out of the objects directory into a new,
+    /// timestamped directory under the quarantine directory.
+    ///
+    /// Returns the directory the entries were moved to.
+    pub fn quarantine(&self, orphans: &[Orphan]) -> Result<PathBuf> {
+        let destination = self
+            .quarantine
+            .join(Local::now().format("%Y%m%dT%H%M%S%.3f").to_string());
+
+        for orphan in orphans {
+            let relative =
+                orphan.path.strip_prefix(&self.objects).map_err(|_| {
+                    Error::Internal(format!(
+                        "'{}' is not in the objects directory",
+                        orphan.path.display()
+                    ))
+                })?;
+            let target = destination.join(relative);
+
+            create_directories(&target)?;
+            fs::rename(&orphan.path, &target).map_err(|err| {
+                Error::Internal(format!(
+                    "Failed to move '{}' to '{}': {err}",
+                    orphan.path.display(),
+                    target.display()
+                ))
+            })?;
+
+            debug!(
+                "Quarantined '{}' to '{}'",
+                orphan.path.display(),
+                target.display()
+            );
+        }
+
+        Ok(destination)
+    }
+
+    pub async fn ranged_part(&self, id: &Uuid) -> Result<RangedPart> {
+        RangedPart::open(id, self.part_path(id), &self.locked_parts).await
+    }
+
     pub async fn remove_extraneous(&self, dest: &Path) -> Result<()> {
         let dest = dest.join(OBJECTS_DIR);
         rm::remove_extraneous(&self.objects, &dest).await
     }
 
+    /// Deletes orphaned object files.
+    pub async fn remove_orphans(&self, orphans: &[Orphan]) -> Result<()> {
+        rm::remove_entries(
+            orphans.iter().map(|orphan| orphan.path.clone()).collect(),
+        )
+        .await
+    }
+
+    /// Deletes a part that is not being written to.
+    ///
+    /// If `modified_before` is given, the part is only deleted if it has
+    /// not been written to since that time. Returns whether the part was
+    /// deleted.
+    pub async fn remove_part(
+        &self,
+        id: &Uuid,
+        modified_before: Option<SystemTime>,
+    ) -> Result<bool> {
+        let part =
+            LockedPart::lock(id, &self.part_path(id), &self.locked_parts)?;
+
+        if let Some(before) = modified_before {
+            if part.modified()? >= before {
+                return Ok(false);
+            }
+        }
+
+        self.discard_part(id).await?;
+        Ok(true)
+    }
+
+    /// Deletes hash state and range files in the parts directory that no
+    /// longer have a part, such as those left by a crash during a commit.
+    /// Files changed since `before` are kept. Returns the deleted paths.
+    pub async fn remove_stale_sidecars(
+        &self,
+        before: SystemTime,
+    ) -> Result<Vec<PathBuf>> {
+        let Scan { unexpected, .. } = scan::scan(&self.parts).await?;
+        let mut removed = Vec::new();
+
+        for Orphan { path, .. } in unexpected {
+            let Some(id) = self.sidecar_id(&path) else {
+                continue;
+            };
+
+            // A locked part may be about to write its sidecars.
+            let Ok(_lock) = self.locked_parts.lock(&id) else {
+                continue;
+            };
+
+            if self.part_path(&id).exists() {
+                continue;
+            }
+
+            match fs::metadata(&path).and_then(|metadata| metadata.modified()) {
+                Ok(modified) if modified < before => (),
+                _ => continue,
+            }
+
+            rm::remove_files(vec![path.clone()]).await?;
+            removed.push(path);
+        }
+
+        Ok(removed)
+    }
+
     pub async fn remove_objects<'a, I>(&self, objects: I) -> Result<()>
     where
         I: Iterator<Item = &'a Uuid>,
@@ -230,4 +556,10 @@ impl Filesystem {
         let paths = objects.map(|id| self.object_path(id)).collect();
         rm::remove_files(paths).await
     }
+
+    /// Sorts the contents of the objects directory into object files and
+    /// entries that should not be there.
+    pub async fn scan_objects(&self) -> Result<Scan> {
+        scan::scan(&self.objects).await
+    }
 }
@@ -1,9 +1,266 @@
 use crate::error::{internal, Error, Result};
 
-use sha2::{Digest, Sha256};
-use std::{fs::File, io, path::Path};
+use log::debug;
+use sha2::{compress256, digest::generic_array::GenericArray, Digest, Sha256};
+use std::{
+    fs::{self, File},
+    io::{self, ErrorKind, Read, Seek, SeekFrom},
+    path::{Path, PathBuf},
+    slice,
+};
 use tokio::task;
 
+const BLOCK_SIZE: usize = 64;
+pub const STATE_EXTENSION: &str = "sha256";
+const STATE_SIZE: usize = 40;
+
+const INITIAL_STATE: [u32; 8] = [
+    0x6a09e667, 0xbb67ae85, 0x3c6ef372, 0xa54ff53a, 0x510e527f, 0x9b05688c,
+    0x1f83d9ab, 0x5be0cd19,
+];
+
+fn encode(hash: &[u8], path: &Path) -> Result<String> {
+    let mut buffer = [0u8; 64];
+    let hex = match base16ct::lower::encode_str(hash, &mut buffer) {
+        Ok(hex) => hex,
+        Err(err) => internal!(
+            "Failed to encode hash of '{}' into string: {err}",
+            path.display()
+        ),
+    };
+
+    Ok(String::from(hex))
+}
+
+fn state_path(path: &Path) -> PathBuf {
+    path.with_extension(STATE_EXTENSION)
+}
+
+/// A SHA-256 hasher whose progress can be saved alongside a part file and
+/// resumed by a later request that appends to the same part.
+#[derive(Clone, Debug)]
+pub struct Hasher {
+    state: [u32; 8],
+    length: u64,
+    buffer: [u8; BLOCK_SIZE],
+    buffered: usize,
+}
+
+impl Default for Hasher {
+    fn default() -> Self {
+        Self {
+            state: INITIAL_STATE,
+            length: 0,
+            buffer: [0; BLOCK_SIZE],
+            buffered: 0,
+        }
+    }
+}
+
+impl Hasher {
+    fn from_saved(bytes: &[u8]) -> Option<Self> {
+        if bytes.len() != STATE_SIZE {
+            return None;
+        }
+
+        let mut state = [0u32; 8];
+        for (word, chunk) in state.iter_mut().zip(bytes.chunks_exact(4)) {
+            *word = u32::from_be_bytes(chunk.try_into().unwrap());
+        }
+
+        let length = u64::from_be_bytes(bytes[32..].try_into().unwrap());
+        if length % BLOCK_SIZE as u64 != 0 {
+            return None;
+        }
+
+        Some(Self {
+            state,
+            length,
+            ..Default::default()
+        })
+    }
+
+    fn compress(&mut self, block: &[u8]) {
+        let block = GenericArray::from_slice(block);
+        compress256(&mut self.state, slice::from_ref(block));
+    }
+
+    fn consume(&mut self, mut data: &[u8]) {
+        if self.buffered > 0 {
+            let count = (BLOCK_SIZE - self.buffered).min(data.len());
+            let end = self.buffered + count;
+
+            self.buffer[self.buffered..end].copy_from_slice(&data[..count]);
+            self.buffered = end;
+            data = &data[count..];
+
+            if self.buffered < BLOCK_SIZE {
+                return;
+            }
+
+            let block = self.buffer;
+            self.compress(&block);
+            self.buffered = 0;
+        }
+
+        let mut blocks = data.chunks_exact(BLOCK_SIZE);
+        for block in &mut blocks {
+            self.compress(block);
+        }
+
+        let remainder = blocks.remainder();
+        self.buffer[..remainder.len()].copy_from_slice(remainder);
+        self.buffered = remainder.len();
+    }
+
+    pub fn update(&mut self, data: &[u8]) {
+        self.length += data.len() as u64;
+        self.consume(data);
+    }
+
+    /// Returns the number of bytes hashed so far.
+    pub fn len(&self) -> u64 {
+        self.length
+    }
+
+    pub fn finalize(mut self, path: &Path) -> Result<String> {
+        let bits = self.length * 8;
+
+        let mut padding = [0u8; BLOCK_SIZE + 8];
+        padding[0] = 0x80;
+
+        let padding_len = if self.buffered < BLOCK_SIZE - 8 {
+            BLOCK_SIZE - 8 - self.buffered
+        } else {
+            2 * BLOCK_SIZE - 8 - self.buffered
+        };
+
+        self.consume(&padding[..padding_len]);
+        self.consume(&bits.to_be_bytes());
+
+        let mut hash = [0u8; 32];
+        for (chunk, word) in hash.chunks_exact_mut(4).zip(self.state) {
+            chunk.copy_from_slice(&word.to_be_bytes());
+        }
+
+        encode(&hash, path)
+    }
+
+    /// Saves the hasher's state next to the part file at `path`.
+    ///
+    /// Only complete blocks are saved: any buffered bytes are read back from
+    /// the end of the part file when the hasher is resumed.
+    pub async fn save(&self, path: &Path) -> Result<()> {
+        let mut bytes = Vec::with_capacity(STATE_SIZE);
+
+        for word in self.state {
+            bytes.extend_from_slice(&word.to_be_bytes());
+        }
+
+        let compressed = self.length - self.buffered as u64;
+        bytes.extend_from_slice(&compressed.to_be_bytes());
+
+        let state = state_path(path);
+        tokio::fs::write(&state, bytes).await.map_err(|err| {
+            Error::Internal(format!(
+                "Failed to save hash state to '{}': {err}",
+                state.display()
+            ))
+        })
+    }
+}
+
+/// Restores the hash state saved for the part file at `path`, hashing any
+/// data written to the part after the state was last saved.
+pub async fn resume(path: &Path) -> Result<Hasher> {
+    let buf = path.to_path_buf();
+    task::spawn_blocking(move || resume_blocking(&buf))
+        .await
+        .map_err(|err| {
+            Error::Internal(format!(
+                "Task failed while resuming hash of '{}': {err}",
+                path.display()
+            ))
+        })?
+}
+
+fn resume_blocking(path: &Path) -> Result<Hasher> {
+    let mut file = match File::open(path) {
+        Ok(file) => file,
+        Err(err) if err.kind() == ErrorKind::NotFound => {
+            return Ok(Default::default())
+        }
+        Err(err) => internal!(
+            "Failed to open file '{}' for hashing: {err}",
+            path.display()
+        ),
+    };
+
+    let state = state_path(path);
+    let saved = match fs::read(&state) {
+        Ok(bytes) => Hasher::from_saved(&bytes),
+        Err(err) if err.kind() == ErrorKind::NotFound => None,
+        Err(err) => {
+            debug!("Failed to read hash state '{}': {err}", state.display());
+            None
+        }
+    };
+
+    let len = match file.metadata() {
+        Ok(metadata) => metadata.len(),
+        Err(err) => internal!(
+            "Failed to fetch metadata for file '{}': {err}",
+            path.display()
+        ),
+    };
+
+    let mut hasher = match saved {
+        Some(hasher) if hasher.len() <= len => hasher,
+        _ => {
+            if len > 0 {
+                debug!(
+                    "No usable hash state for '{}': rehashing file",
+                    path.display()
+                );
+            }
+
+            Hasher::default()
+        }
+    };
+
+    if let Err(err) = file.seek(SeekFrom::Start(hasher.len())) {
+        internal!("Failed to seek in file '{}': {err}", path.display());
+    }
+
+    let mut buffer = vec![0u8; 64 * 1024];
+    loop {
+        match file.read(&mut buffer) {
+            Ok(0) => break,
+            Ok(n) => hasher.update(&buffer[..n]),
+            Err(err) if err.kind() == ErrorKind::Interrupted => continue,
+            Err(err) => {
+                internal!("Failed to hash file '{}': {err}", path.display())
+            }
+        }
+    }
+
+    Ok(hasher)
+}
+
+/// Removes the hash state saved for the part file at `path`, if any.
+pub fn remove_state(path: &Path) -> Result<()> {
+    let state = state_path(path);
+
+    match fs::remove_file(&state) {
+        Ok(()) => Ok(()),
+        Err(err) if err.kind() == ErrorKind::NotFound => Ok(()),
+        Err(err) => internal!(
+            "Failed to remove hash state '{}': {err}",
+            state.display()
+        ),
+    }
+}
+
 pub async fn sha256sum(path: &Path) -> Result<String> {
     let buf = path.to_path_buf();
     task::spawn_blocking(move || sha256sum_blocking(&buf))
@@ -31,14 +288,128 @@ fn sha256sum_blocking(path: &Path) -> Result<String> {
     }
     let hash = hasher.finalize();
 
-    let mut buffer = [0u8; 64];
-    let hex = match base16ct::lower::encode_str(&hash, &mut buffer) {
-        Ok(hex) => hex,
-        Err(err) => internal!(
-            "Failed to encode hash of '{}' into string: {err}",
-            path.display()
-        ),
-    };
+    encode(&hash, path)
+}
 
-    Ok(String::from(hex))
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn bytes(len: usize) -> Vec<u8> {
+        (0..len).map(|i| (i % 251) as u8).collect()
+    }
+
+    fn expected(data: &[u8]) -> String {
+        encode(&Sha256::digest(data), Path::new("test")).unwrap()
+    }
+
+    fn hash_chunks(data: &[u8], chunk_size: usize) -> String {
+        let mut hasher = Hasher::default();
+
+        for chunk in data.chunks(chunk_size) {
+            hasher.update(chunk);
+        }
+
+        hasher.finalize(Path::new("test")).unwrap()
+    }
+
+    #[test]
+    fn matches_sha256() {
+        for len in [0, 1, 55, 56, 63, 64, 65, 119, 120, 128, 1000, 100_000] {
+            let data = bytes(len);
+            assert_eq!(
+                hash_chunks(&data, len.max(1)),
+                expected(&data),
+                "length {len}"
+            );
+        }
+    }
+
+    #[test]
+    fn matches_sha256_in_chunks() {
+        let data = bytes(1000);
+
+        for chunk_size in [1, 7, 55, 63, 64, 65, 100, 999] {
+            assert_eq!(
+                hash_chunks(&data, chunk_size),
+                expected(&data),
+                "chunk size {chunk_size}"
+            );
+        }
+    }
+
+    #[test]
+    fn counts_hashed_bytes() {
+        let mut hasher = Hasher::default();
+        hasher.update(&bytes(100));
+        hasher.update(&bytes(30));
+
+        assert_eq!(hasher.len(), 130);
+    }
+
+    #[test]
+    fn rejects_invalid_saved_state() {
+        assert!(Hasher::from_saved(&[0; STATE_SIZE - 1]).is_none());
+
+        let mut bytes = [0; STATE_SIZE];
+        bytes[STATE_SIZE - 1] = 1;
+        assert!(Hasher::from_saved(&bytes).is_none());
+    }
+
+    #[tokio::test]
+    async fn resumes_saved_state() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("part");
+        let data = bytes(1000);
+
+        for saved in [0, 1, 63, 64, 100, 128, 999, 1000] {
+            std::fs::write(&path, &data).unwrap();
+
+            let mut hasher = Hasher::default();
+            hasher.update(&data[..saved]);
+            hasher.save(&path).await.unwrap();
+
+            let resumed = resume(&path).await.unwrap();
+            assert_eq!(resumed.len(), data.len() as u64);
+            assert_eq!(
+                resumed.finalize(&path).unwrap(),
+                expected(&data),
+                "saved after {saved} bytes"
+            );
+        }
+    }
+
+    #[tokio::test]
+    async fn rehashes_without_usable_state() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("part");
+        let data = bytes(300);
+
+        std::fs::write(&path, &data).unwrap();
+        let hasher = resume(&path).await.unwrap();
+        assert_eq!(hasher.finalize(&path).unwrap(), expected(&data));
+
+        // State saved for more data than the file holds is ignored.
+        let mut hasher = Hasher::default();
+        hasher.update(&bytes(640));
+        hasher.save(&path).await.unwrap();
+
+        let hasher = resume(&path).await.unwrap();
+        assert_eq!(hasher.finalize(&path).unwrap(), expected(&data));
+    }
+
+    #[tokio::test]
+    async fn removes_saved_state() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("part");
+
+        Hasher::default().save(&path).await.unwrap();
+        assert!(state_path(&path).exists());
+
+        remove_state(&path).unwrap();
+        assert!(!state_path(&path).exists());
+
+        // Removing state that does not exist is not an error.
+        remove_state(&path).unwrap();
+    }
 }
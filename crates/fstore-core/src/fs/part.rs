@@ -1,38 +1,68 @@
 mod lock;
+mod range;
+
+pub use range::{
+    check_complete, load as load_ranges, remove as remove_ranges,
+    RANGES_EXTENSION,
+};
 
 use lock::FileLock;
 
-use super::create_directories;
+use super::{
+    create_directories,
+    hash::{self, Hasher},
+};
 
 use crate::error::{Error, Result};
 
 use bytes::Bytes;
-use futures::{pin_mut, Stream, TryStreamExt};
+use futures::{pin_mut, Stream, StreamExt, TryStreamExt};
 use log::debug;
 use std::{
-    collections::HashSet,
+    collections::{hash_map::Entry, HashMap},
+    fs,
+    io::{ErrorKind, SeekFrom},
     os::fd::AsRawFd,
-    path::PathBuf,
+    path::{Path, PathBuf},
     sync::{Arc, Mutex},
+    time::SystemTime,
+};
+use tokio::{
+    fs::File,
+    io::{AsyncSeekExt, AsyncWriteExt},
 };
-use tokio::fs::File;
 use tokio_util::io::StreamReader;
 use uuid::Uuid;
 
+#[derive(Clone, Copy, Debug)]
+enum LockState {
+    Exclusive,
+    Shared(usize),
+}
+
 pub struct PartLock {
     id: Uuid,
-    storage: Arc<Mutex<HashSet<Uuid>>>,
+    storage: Arc<Mutex<HashMap<Uuid, LockState>>>,
 }
 
 impl Drop for PartLock {
     fn drop(&mut self) {
-        self.storage.lock().unwrap().remove(&self.id);
+        let mut storage = self.storage.lock().unwrap();
+
+        if let Some(LockState::Shared(count)) = storage.get_mut(&self.id) {
+            if *count > 1 {
+                *count -= 1;
+                return;
+            }
+        }
+
+        storage.remove(&self.id);
     }
 }
 
 #[derive(Debug, Default)]
 pub struct PartLockSet {
-    storage: Arc<Mutex<HashSet<Uuid>>>,
+    storage: Arc<Mutex<HashMap<Uuid, LockState>>>,
 }
 
 impl PartLockSet {
@@ -41,22 +71,91 @@ impl PartLockSet {
     }
 
     pub fn lock(&self, id: &Uuid) -> Result<PartLock> {
-        if self.storage.lock().unwrap().insert(*id) {
-            Ok(PartLock {
-                id: *id,
-                storage: self.storage.clone(),
-            })
-        } else {
-            Err(Error::WriteLock)
+        match self.storage.lock().unwrap().entry(*id) {
+            Entry::Occupied(_) => Err(Error::WriteLock),
+            Entry::Vacant(entry) => {
+                entry.insert(LockState::Exclusive);
+                Ok(self.part_lock(id))
+            }
+        }
+    }
+
+    /// Locks a part for writing alongside other shared lock holders.
+    pub fn lock_shared(&self, id: &Uuid) -> Result<PartLock> {
+        match self.storage.lock().unwrap().entry(*id) {
+            Entry::Occupied(mut entry) => match entry.get_mut() {
+                LockState::Exclusive => Err(Error::WriteLock),
+                LockState::Shared(count) => {
+                    *count += 1;
+                    Ok(self.part_lock(id))
+                }
+            },
+            Entry::Vacant(entry) => {
+                entry.insert(LockState::Shared(1));
+                Ok(self.part_lock(id))
+            }
+        }
+    }
+
+    fn part_lock(&self, id: &Uuid) -> PartLock {
+        PartLock {
+            id: *id,
+            storage: self.storage.clone(),
         }
     }
 }
 
+/// Exclusive access to a part file without opening it for writing.
+pub struct LockedPart {
+    _internal_lock: PartLock,
+    _external_lock: FileLock,
+    file: fs::File,
+}
+
+impl LockedPart {
+    pub fn lock(id: &Uuid, path: &Path, locks: &PartLockSet) -> Result<Self> {
+        let internal_lock = locks.lock(id)?;
+
+        let file = match fs::File::open(path) {
+            Ok(file) => file,
+            Err(err) if err.kind() == ErrorKind::NotFound => {
+                return Err(Error::NotFound("part"))
+            }
+            Err(err) => {
+                return Err(Error::Internal(format!(
+                    "Failed to open part file '{}': {err}",
+                    path.display()
+                )))
+            }
+        };
+
+        let external_lock = lock::exclusive(file.as_raw_fd())?;
+
+        Ok(Self {
+            _internal_lock: internal_lock,
+            _external_lock: external_lock,
+            file,
+        })
+    }
+
+    pub fn modified(&self) -> Result<SystemTime> {
+        self.file
+            .metadata()
+            .and_then(|metadata| metadata.modified())
+            .map_err(|err| {
+                Error::Internal(format!(
+                    "Failed to read modification time of part file: {err}"
+                ))
+            })
+    }
+}
+
 pub struct Part {
     internal_lock: PartLock,
     _external_lock: FileLock,
     path: PathBuf,
     file: tokio::fs::File,
+    hasher: Hasher,
 }
 
 impl Part {
@@ -68,6 +167,13 @@ impl Part {
         let internal_lock = locks.lock(id)?;
         create_directories(&path)?;
 
+        if range::exists(&path) {
+            return Err(Error::InvalidPart(format!(
+                "Part {id} is being written at explicit offsets \
+                and cannot be appended to"
+            )));
+        }
+
         let file = File::options()
             .create(true)
             .append(true)
@@ -81,12 +187,14 @@ impl Part {
             })?;
 
         let external_lock = lock::exclusive(file.as_raw_fd())?;
+        let hasher = hash::resume(&path).await?;
 
         Ok(Part {
             internal_lock,
             _external_lock: external_lock,
             path,
             file,
+            hasher,
         })
     }
 
@@ -94,12 +202,20 @@ impl Part {
         &self.internal_lock.id
     }
 
+    /// Returns the total number of bytes written to the part.
+    pub fn size(&self) -> u64 {
+        self.hasher.len()
+    }
+
     pub async fn stream_to_file<S, E>(&mut self, stream: S) -> Result<u64>
     where
         S: Stream<Item = std::result::Result<Bytes, E>>,
         E: Into<Box<dyn std::error::Error + Send + Sync>>,
     {
-        let stream = stream.map_err(std::io::Error::other);
+        let hasher = &mut self.hasher;
+        let stream = stream
+            .map_err(std::io::Error::other)
+            .inspect_ok(|bytes| hasher.update(bytes));
         let reader = StreamReader::new(stream);
         pin_mut!(reader);
 
@@ -112,6 +228,8 @@ impl Part {
                 ))
             })?;
 
+        self.hasher.save(&self.path).await?;
+
         debug!(
             "Wrote {bytes} byte{} to part file '{}'",
             match bytes {
@@ -124,3 +242,121 @@ impl Part {
         Ok(bytes)
     }
 }
+
+/// A part opened for writing at explicit offsets.
+///
+/// Unlike [`Part`], several requests may write to different ranges of the
+/// same part at once. The ranges written are recorded so that the part can
+/// be checked for gaps before it is committed.
+pub struct RangedPart {
+    _internal_lock: PartLock,
+    _external_lock: FileLock,
+    path: PathBuf,
+    file: File,
+}
+
+impl RangedPart {
+    pub async fn open(
+        id: &Uuid,
+        path: PathBuf,
+        locks: &PartLockSet,
+    ) -> Result<Self> {
+        let internal_lock = locks.lock_shared(id)?;
+        create_directories(&path)?;
+
+        let file = File::options()
+            .create(true)
+            .truncate(false)
+            .write(true)
+            .open(&path)
+            .await
+            .map_err(|err| {
+                Error::Internal(format!(
+                    "Failed to open part file '{}': {err}",
+                    path.display()
+                ))
+            })?;
+
+        let external_lock = lock::shared(file.as_raw_fd())?;
+
+        range::begin(&path).await?;
+        hash::remove_state(&path)?;
+
+        Ok(Self {
+            _internal_lock: internal_lock,
+            _external_lock: external_lock,
+            path,
+            file,
+        })
+    }
+
+    /// Writes the stream's data to the part starting at `offset`.
+    ///
+    /// Data written before an error occurs is still recorded.
+    pub async fn write_at<S, E>(
+        &mut self,
+        offset: u64,
+        stream: S,
+    ) -> Result<u64>
+    where
+        S: Stream<Item = std::result::Result<Bytes, E>>,
+        E: Into<Box<dyn std::error::Error + Send + Sync>>,
+    {
+        let mut written = 0;
+        let result = self.write_stream(offset, stream, &mut written).await;
+
+        range::record(&self.path, offset..offset + written).await?;
+        result?;
+
+        debug!(
+            "Wrote {written} byte{} at offset {offset} to part file '{}'",
+            match written {
+                1 => "",
+                _ => "s",
+            },
+            self.path.display()
+        );
+
+        Ok(written)
+    }
+
+    async fn write_stream<S, E>(
+        &mut self,
+        offset: u64,
+        stream: S,
+        written: &mut u64,
+    ) -> Result<()>
+    where
+        S: Stream<Item = std::result::Result<Bytes, E>>,
+        E: Into<Box<dyn std::error::Error + Send + Sync>>,
+    {
+        let error = |err: Box<dyn std::error::Error + Send + Sync>| {
+            Error::Internal(format!(
+                "Failed to write stream data to part file '{}' \
+                at offset {offset}: {err}",
+                self.path.display()
+            ))
+        };
+
+        self.file
+            .seek(SeekFrom::Start(offset))
+            .await
+            .map_err(|err| error(err.into()))?;
+
+        pin_mut!(stream);
+
+        while let Some(chunk) = stream.next().await {
+            let chunk = chunk.map_err(|err| error(err.into()))?;
+
+            self.file
+                .write_all(&chunk)
+                .await
+                .map_err(|err| error(err.into()))?;
+            self.file.flush().await.map_err(|err| error(err.into()))?;
+
+            *written += chunk.len() as u64;
+        }
+
+        Ok(())
+    }
+}
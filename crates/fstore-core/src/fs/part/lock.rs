@@ -1,6 +1,6 @@
 use crate::error::{internal, Error, Result};
 
-use libc::{c_int, LOCK_EX, LOCK_NB, LOCK_UN};
+use libc::{c_int, LOCK_EX, LOCK_NB, LOCK_SH, LOCK_UN};
 use log::error;
 use std::{
     io::{self, ErrorKind},
@@ -40,3 +40,27 @@ pub fn exclusive(fd: RawFd) -> Result<FileLock> {
 
     Ok(FileLock { fd })
 }
+
+pub fn shared(fd: RawFd) -> Result<FileLock> {
+    if let Err(err) = flock(fd, LOCK_SH | LOCK_NB) {
+        match err.kind() {
+            ErrorKind::WouldBlock => return Err(Error::WriteLock),
+            _ => internal!(
+                "Failed to acquire file lock for fd ({}): {}",
+                fd,
+                err
+            ),
+        }
+    }
+
+    Ok(FileLock { fd })
+}
+
+/// Acquires an exclusive lock, blocking until any other lock is released.
+pub fn wait_exclusive(fd: RawFd) -> Result<FileLock> {
+    if let Err(err) = flock(fd, LOCK_EX) {
+        internal!("Failed to acquire file lock for fd ({}): {}", fd, err);
+    }
+
+    Ok(FileLock { fd })
+}
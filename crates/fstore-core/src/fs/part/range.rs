@@ -0,0 +1,377 @@
+use super::lock;
+
+use crate::error::{internal, Error, Result};
+
+use std::{
+    fs::{self, File},
+    io::{ErrorKind, Read, Seek, SeekFrom, Write},
+    ops::Range,
+    os::fd::AsRawFd,
+    path::{Path, PathBuf},
+};
+use tokio::task;
+use uuid::Uuid;
+
+pub const RANGES_EXTENSION: &str = "ranges";
+
+fn ranges_path(path: &Path) -> PathBuf {
+    path.with_extension(RANGES_EXTENSION)
+}
+
+fn parse(text: &str, path: &Path) -> Result<Vec<Range<u64>>> {
+    let mut ranges = Vec::new();
+
+    for line in text.lines().filter(|line| !line.is_empty()) {
+        let range = line.split_once('-').and_then(|(start, end)| {
+            Some(start.parse().ok()?..end.parse().ok()?)
+        });
+
+        match range {
+            Some(range) => ranges.push(range),
+            None => internal!(
+                "Invalid line in part ranges file '{}': {}",
+                path.display(),
+                line
+            ),
+        }
+    }
+
+    Ok(ranges)
+}
+
+/// Adds `range` to a sorted list of disjoint ranges, combining it with any
+/// ranges it overlaps or touches.
+fn merge(ranges: &mut Vec<Range<u64>>, mut range: Range<u64>) {
+    if range.is_empty() {
+        return;
+    }
+
+    let mut merged = Vec::with_capacity(ranges.len() + 1);
+    let mut inserted = false;
+
+    for existing in ranges.drain(..) {
+        if existing.end < range.start {
+            merged.push(existing);
+        } else if range.end < existing.start {
+            if !inserted {
+                merged.push(range.clone());
+                inserted = true;
+            }
+            merged.push(existing);
+        } else {
+            range =
+                existing.start.min(range.start)..existing.end.max(range.end);
+        }
+    }
+
+    if !inserted {
+        merged.push(range);
+    }
+
+    *ranges = merged;
+}
+
+/// Returns an error describing the first gap in `ranges` if they do not
+/// cover exactly the bytes of a file of the given `size`.
+pub fn check_complete(ranges: &[Range<u64>], size: u64) -> Result<()> {
+    let mut position = 0;
+
+    for range in ranges {
+        if range.start > position {
+            break;
+        }
+
+        position = range.end;
+    }
+
+    if position < size {
+        let end = ranges
+            .iter()
+            .map(|range| range.start)
+            .find(|start| *start > position)
+            .unwrap_or(size);
+
+        return Err(Error::InvalidPart(format!(
+            "Part is incomplete: bytes {position}-{end} have not been written"
+        )));
+    }
+
+    if position > size {
+        return Err(Error::InvalidPart(format!(
+            "Part is larger than expected: {position} bytes have been \
+            written, expected {size}"
+        )));
+    }
+
+    Ok(())
+}
+
+/// Starts tracking the ranges written to a part, if not already tracked.
+///
+/// Any data already in the part is assumed to have been appended
+/// sequentially and is recorded as the first range.
+pub async fn begin(path: &Path) -> Result<()> {
+    let path = path.to_path_buf();
+    spawn(move || blocking::begin(&path)).await
+}
+
+/// Returns whether a part is being written to at explicit offsets.
+pub fn exists(path: &Path) -> bool {
+    ranges_path(path).exists()
+}
+
+/// Returns the ranges written to a part, or `None` if the part was written
+/// to sequentially.
+pub async fn load(path: &Path) -> Result<Option<Vec<Range<u64>>>> {
+    let path = path.to_path_buf();
+    spawn(move || blocking::load(&path)).await
+}
+
+pub async fn record(path: &Path, range: Range<u64>) -> Result<()> {
+    let path = path.to_path_buf();
+    spawn(move || blocking::update(&path, range)).await
+}
+
+pub fn remove(path: &Path) -> Result<()> {
+    let ranges = ranges_path(path);
+
+    match fs::remove_file(&ranges) {
+        Ok(()) => Ok(()),
+        Err(err) if err.kind() == ErrorKind::NotFound => Ok(()),
+        Err(err) => internal!(
+            "Failed to remove part ranges '{}': {err}",
+            ranges.display()
+        ),
+    }
+}
+
+async fn spawn<F, T>(f: F) -> Result<T>
+where
+    F: FnOnce() -> Result<T> + Send + 'static,
+    T: Send + 'static,
+{
+    task::spawn_blocking(f).await.map_err(|err| {
+        Error::Internal(format!(
+            "Task failed while tracking part ranges: {err}"
+        ))
+    })?
+}
+
+mod blocking {
+    use super::*;
+
+    pub fn load(path: &Path) -> Result<Option<Vec<Range<u64>>>> {
+        let ranges = ranges_path(path);
+
+        match fs::read_to_string(&ranges) {
+            Ok(text) => Ok(Some(parse(&text, &ranges)?)),
+            Err(err) if err.kind() == ErrorKind::NotFound => Ok(None),
+            Err(err) => internal!(
+                "Failed to read part ranges '{}': {err}",
+                ranges.display()
+            ),
+        }
+    }
+
+    pub fn begin(path: &Path) -> Result<()> {
+        let ranges = ranges_path(path);
+        if ranges.exists() {
+            return Ok(());
+        }
+
+        let size = match fs::metadata(path) {
+            Ok(metadata) => metadata.len(),
+            Err(err) if err.kind() == ErrorKind::NotFound => 0,
+            Err(err) => internal!(
+                "Failed to fetch metadata for part file '{}': {err}",
+                path.display()
+            ),
+        };
+
+        // Write the initial ranges to a temporary file and link it into
+        // place so that concurrent writers never see a partial file.
+        let temp = ranges
+            .with_extension(format!("{RANGES_EXTENSION}.{}", Uuid::new_v4()));
+        let text = match size {
+            0 => String::new(),
+            size => format!("0-{size}\n"),
+        };
+
+        fs::write(&temp, text).map_err(|err| {
+            Error::Internal(format!(
+                "Failed to write part ranges '{}': {err}",
+                temp.display()
+            ))
+        })?;
+
+        let result = fs::hard_link(&temp, &ranges);
+        fs::remove_file(&temp).ok();
+
+        match result {
+            Ok(()) => Ok(()),
+            Err(err) if err.kind() == ErrorKind::AlreadyExists => Ok(()),
+            Err(err) => internal!(
+                "Failed to create part ranges '{}': {err}",
+                ranges.display()
+            ),
+        }
+    }
+
+    pub fn update(path: &Path, range: Range<u64>) -> Result<()> {
+        let ranges_path = ranges_path(path);
+        let error = |err| {
+            Error::Internal(format!(
+                "Failed to update part ranges '{}': {err}",
+                ranges_path.display()
+            ))
+        };
+
+        let mut file = File::options()
+            .read(true)
+            .write(true)
+            .create(true)
+            .truncate(false)
+            .open(&ranges_path)
+            .map_err(error)?;
+        let _lock = lock::wait_exclusive(file.as_raw_fd())?;
+
+        let mut text = String::new();
+        file.read_to_string(&mut text).map_err(error)?;
+
+        let mut ranges = parse(&text, &ranges_path)?;
+        merge(&mut ranges, range);
+
+        let mut text = String::new();
+        for range in &ranges {
+            text.push_str(&format!("{}-{}\n", range.start, range.end));
+        }
+
+        file.set_len(0).map_err(error)?;
+        file.seek(SeekFrom::Start(0)).map_err(error)?;
+        file.write_all(text.as_bytes()).map_err(error)?;
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+#[allow(clippy::single_range_in_vec_init)]
+mod tests {
+    use super::*;
+
+    fn merged(writes: &[Range<u64>]) -> Vec<Range<u64>> {
+        let mut ranges = Vec::new();
+
+        for range in writes {
+            merge(&mut ranges, range.clone());
+        }
+
+        ranges
+    }
+
+    #[test]
+    fn merges_overlapping_ranges() {
+        assert_eq!(merged(&[0..10, 5..15]), [0..15]);
+        assert_eq!(merged(&[5..15, 0..10]), [0..15]);
+        assert_eq!(merged(&[0..20, 5..10]), [0..20]);
+        assert_eq!(merged(&[0..5, 10..15, 3..12]), [0..15]);
+    }
+
+    #[test]
+    fn merges_adjacent_ranges() {
+        assert_eq!(merged(&[0..10, 10..20]), [0..20]);
+        assert_eq!(merged(&[10..20, 0..10]), [0..20]);
+        assert_eq!(merged(&[0..5, 10..15, 5..10]), [0..15]);
+    }
+
+    #[test]
+    fn sorts_disjoint_ranges() {
+        assert_eq!(merged(&[20..30, 0..10]), [0..10, 20..30]);
+        assert_eq!(merged(&[40..50, 0..10, 20..30]), [0..10, 20..30, 40..50]);
+    }
+
+    #[test]
+    fn ignores_zero_length_writes() {
+        assert_eq!(merged(&[5..5]), []);
+        assert_eq!(merged(&[0..10, 20..20]), [0..10]);
+    }
+
+    #[test]
+    fn accepts_complete_ranges() {
+        assert!(check_complete(&[0..100], 100).is_ok());
+        assert!(check_complete(&[], 0).is_ok());
+    }
+
+    #[test]
+    fn rejects_gaps() {
+        let message = |ranges: &[Range<u64>], size| {
+            check_complete(ranges, size).unwrap_err().to_string()
+        };
+
+        assert_eq!(
+            message(&[], 100),
+            "Part is incomplete: bytes 0-100 have not been written"
+        );
+        assert_eq!(
+            message(&[10..100], 100),
+            "Part is incomplete: bytes 0-10 have not been written"
+        );
+        assert_eq!(
+            message(&[0..10, 20..100], 100),
+            "Part is incomplete: bytes 10-20 have not been written"
+        );
+        assert_eq!(
+            message(&[0..90], 100),
+            "Part is incomplete: bytes 90-100 have not been written"
+        );
+    }
+
+    #[test]
+    fn rejects_writes_past_size() {
+        assert_eq!(
+            check_complete(&[0..120], 100).unwrap_err().to_string(),
+            "Part is larger than expected: 120 bytes have been written, \
+            expected 100"
+        );
+        assert!(check_complete(&[0..50], 0).is_err());
+    }
+
+    #[tokio::test]
+    async fn records_ranges() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("part");
+
+        assert!(!exists(&path));
+        assert_eq!(load(&path).await.unwrap(), None);
+
+        begin(&path).await.unwrap();
+        assert!(exists(&path));
+        assert_eq!(load(&path).await.unwrap(), Some(vec![]));
+
+        for range in [20..30, 0..10, 10..15, 40..40] {
+            record(&path, range).await.unwrap();
+        }
+
+        assert_eq!(load(&path).await.unwrap(), Some(vec![0..15, 20..30]));
+
+        remove(&path).unwrap();
+        assert!(!exists(&path));
+        remove(&path).unwrap();
+    }
+
+    #[tokio::test]
+    async fn begins_with_appended_data() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("part");
+
+        fs::write(&path, [0; 25]).unwrap();
+        begin(&path).await.unwrap();
+        record(&path, 25..50).await.unwrap();
+
+        assert_eq!(load(&path).await.unwrap(), Some(vec![0..50]));
+
+        // Beginning again keeps the ranges already recorded.
+        begin(&path).await.unwrap();
+        assert_eq!(load(&path).await.unwrap(), Some(vec![0..50]));
+    }
+}
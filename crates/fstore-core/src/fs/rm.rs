@@ -56,6 +56,30 @@ pub async fn remove_files(paths: Vec<PathBuf>) -> Result<()> {
     }
 }
 
+/// Removes files or entire directories.
+pub async fn remove_entries(paths: Vec<PathBuf>) -> Result<()> {
+    let len = paths.len();
+
+    let result = task::spawn_blocking(move || -> Result<()> {
+        for path in paths {
+            blocking::remove_entry(&path)?;
+        }
+
+        Ok(())
+    })
+    .await;
+
+    match result {
+        Ok(result) => result,
+        Err(_) => {
+            internal!(
+                "failed to remove {} entries: background task failed",
+                len
+            )
+        }
+    }
+}
+
 mod blocking {
     use super::*;
 
@@ -93,6 +117,24 @@ mod blocking {
         Ok(())
     }
 
+    pub fn remove_entry(path: &Path) -> Result<()> {
+        let result = match fs::symlink_metadata(path) {
+            Ok(metadata) if metadata.is_dir() => fs::remove_dir_all(path),
+            Ok(_) => fs::remove_file(path),
+            Err(err) => Err(err),
+        };
+
+        match result {
+            Ok(()) => debug!("Removed '{}'", path.display()),
+            Err(err) if err.kind() == ErrorKind::NotFound => (),
+            Err(err) => {
+                internal!("failed to remove '{}': {}", path.display(), err)
+            }
+        }
+
+        Ok(())
+    }
+
     pub fn remove_extraneous(src: &Path, dest: &Path) -> Result<()> {
         if !dest.exists() {
             return Ok(());
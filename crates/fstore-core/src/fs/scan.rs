@@ -0,0 +1,173 @@
+use super::{path_for_id, ID_SLICES};
+
+use crate::error::{internal, Error, Result};
+
+use std::{
+    fs::Metadata,
+    os::unix::fs::MetadataExt,
+    path::{Path, PathBuf},
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+use tokio::task;
+use uuid::Uuid;
+use walkdir::WalkDir;
+
+#[derive(Clone, Copy, Debug)]
+pub struct FileInfo {
+    pub id: Uuid,
+    pub size: u64,
+
+    /// The time the file's contents were last modified.
+    pub modified: SystemTime,
+
+    /// The time the file's inode was last changed, such as by a rename.
+    pub changed: SystemTime,
+}
+
+impl FileInfo {
+    pub fn new(id: Uuid, metadata: &Metadata) -> Self {
+        let changed = UNIX_EPOCH
+            + Duration::new(
+                metadata.ctime().try_into().unwrap_or_default(),
+                metadata.ctime_nsec().try_into().unwrap_or_default(),
+            );
+
+        Self {
+            id,
+            size: metadata.len(),
+            modified: metadata.modified().unwrap_or(changed),
+            changed,
+        }
+    }
+}
+
+impl From<FileInfo> for fstore::Part {
+    fn from(value: FileInfo) -> Self {
+        Self {
+            id: value.id,
+            size: value.size,
+            modified: value.modified.into(),
+        }
+    }
+}
+
+/// A directory entry that should not exist in the object store.
+#[derive(Clone, Debug)]
+pub struct Orphan {
+    pub path: PathBuf,
+    pub reason: &'static str,
+}
+
+#[derive(Debug, Default)]
+pub struct Scan {
+    /// UUID-named regular files stored at the expected location.
+    pub files: Vec<FileInfo>,
+
+    /// Entries that are not correctly placed, UUID-named regular files.
+    pub unexpected: Vec<Orphan>,
+}
+
+/// Lists the UUID-named regular files stored under `dir`.
+pub async fn files(dir: &Path) -> Result<Vec<FileInfo>> {
+    Ok(scan(dir).await?.files)
+}
+
+/// Walks the ID-sliced directory tree under `dir`, sorting its entries into
+/// valid files and unexpected entries.
+pub async fn scan(dir: &Path) -> Result<Scan> {
+    let directory = dir.to_owned();
+
+    let result = task::spawn_blocking(move || blocking::scan(&directory)).await;
+
+    match result {
+        Ok(result) => result,
+        Err(_) => internal!(
+            "failed to scan files in '{}': background task failed",
+            dir.display()
+        ),
+    }
+}
+
+mod blocking {
+    use super::*;
+
+    pub fn scan(dir: &Path) -> Result<Scan> {
+        let mut files = Vec::new();
+        let mut orphans = Vec::new();
+
+        if !dir.exists() {
+            return Ok(Scan::default());
+        }
+
+        let mut unexpected = |path: &Path, reason| {
+            orphans.push(Orphan {
+                path: path.to_owned(),
+                reason,
+            })
+        };
+
+        let mut walker = WalkDir::new(dir).min_depth(1).into_iter();
+
+        while let Some(entry) = walker.next() {
+            let entry = entry.map_err(|err| {
+                Error::Internal(format!(
+                    "failed to scan files in '{}': {err}",
+                    dir.display()
+                ))
+            })?;
+
+            let path = entry.path();
+            let file_type = entry.file_type();
+
+            if entry.depth() <= ID_SLICES {
+                if !file_type.is_dir() {
+                    unexpected(path, "not inside an ID directory");
+                }
+                continue;
+            }
+
+            if file_type.is_dir() {
+                unexpected(path, "unexpected directory");
+                walker.skip_current_dir();
+                continue;
+            }
+
+            if !file_type.is_file() {
+                unexpected(path, "not a regular file");
+                continue;
+            }
+
+            let Some(name) = entry.file_name().to_str() else {
+                unexpected(path, "name is not valid UTF-8");
+                continue;
+            };
+
+            let id = match Uuid::try_parse(name) {
+                Ok(id) if id.to_string() == name => id,
+                _ => {
+                    unexpected(path, "name is not a valid UUID");
+                    continue;
+                }
+            };
+
+            if path_for_id(dir, &id) != path {
+                unexpected(path, "file is in the wrong directory");
+                continue;
+            }
+
+            let metadata = entry.metadata().map_err(|err| {
+                Error::Internal(format!(
+                    "failed to read metadata for '{}': {err}",
+                    path.display()
+                ))
+            })?;
+
+            files.push(FileInfo::new(id, &metadata));
+        }
+
+        Ok(Scan {
+            files,
+            unexpected: orphans,
+        })
+    }
+}
@@ -4,9 +4,10 @@ mod fs;
 mod model;
 mod progress;
 mod store;
+mod token;
 
 pub use error::Error;
-pub use fs::{File, Part};
+pub use fs::{File, Orphan, Part, RangedPart};
 pub use model::*;
 pub use progress::Progress;
 pub use store::*;
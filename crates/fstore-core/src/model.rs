@@ -1,4 +1,10 @@
+use chrono::{DateTime, Local};
 use serde::Serialize;
+use std::{
+    fmt::{self, Display},
+    str::FromStr,
+};
+use uuid::Uuid;
 
 #[derive(Serialize, Debug, Clone, Copy)]
 pub struct About {
@@ -17,3 +23,82 @@ pub struct Version {
     pub rust_version: &'static str,
     pub rust_channel: &'static str,
 }
+
+/// The operations an API token permits. Each scope includes the ones before
+/// it.
+#[derive(
+    Serialize, Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash,
+)]
+#[serde(rename_all = "lowercase")]
+pub enum Scope {
+    /// Retrieve buckets and objects.
+    Read,
+
+    /// Add, change and remove buckets and objects.
+    Write,
+
+    /// Manage the store as a whole, such as pruning unreferenced objects.
+    Admin,
+}
+
+impl Scope {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Read => "read",
+            Self::Write => "write",
+            Self::Admin => "admin",
+        }
+    }
+}
+
+impl Display for Scope {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl FromStr for Scope {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "read" => Ok(Self::Read),
+            "write" => Ok(Self::Write),
+            "admin" => Ok(Self::Admin),
+            _ => Err(format!("unknown scope '{s}'")),
+        }
+    }
+}
+
+/// An API token used to authenticate requests.
+#[derive(Serialize, Debug, Clone)]
+pub struct Token {
+    pub id: Uuid,
+    pub name: String,
+    pub scope: Scope,
+
+    /// The buckets the token may access, or `None` for every bucket.
+    pub buckets: Option<Vec<Uuid>>,
+
+    pub created: DateTime<Local>,
+}
+
+impl Token {
+    /// Returns whether the token permits operations requiring `scope`.
+    pub fn permits(&self, scope: Scope) -> bool {
+        self.scope >= scope
+    }
+
+    /// Returns whether the token may access the given bucket.
+    pub fn can_access(&self, bucket: &Uuid) -> bool {
+        match &self.buckets {
+            Some(buckets) => buckets.contains(bucket),
+            None => true,
+        }
+    }
+
+    /// Returns whether the token is limited to specific buckets.
+    pub fn is_restricted(&self) -> bool {
+        self.buckets.is_some()
+    }
+}
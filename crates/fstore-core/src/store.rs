@@ -1,26 +1,44 @@
 use crate::{
     db::{self, Database},
     error::{Error, OptionNotFound, Result},
-    fs::{Filesystem, Part},
+    fs::{Filesystem, Orphan, Part, RangedPart, Scan},
     model::*,
     progress::{Progress, ProgressGuard, Task},
-    DbConnection, DbSupport,
+    token, DbConnection, DbSupport,
 };
 
 use chrono::{DateTime, Local};
-use fstore::{Bucket, Object, ObjectError, RemoveResult, StoreTotals};
-use futures::stream::StreamExt;
-use log::{error, info, trace};
+use fstore::{
+    Bucket, BucketPatch, KeyList, LabelSelector, Labels, Metadata,
+    MetadataPatch, Object, ObjectError, ObjectPage, ObjectQuery, PartRange,
+    RemoveResult, StoreTotals,
+};
+use futures::{
+    channel::mpsc,
+    pin_mut,
+    stream::{self, Stream, StreamExt},
+    SinkExt,
+};
+use log::{debug, error, info, trace};
 use pgtools::{PgDump, PgRestore, Psql};
 use serde::{Deserialize, Serialize};
+use sqlx::types::Json;
 use std::{
+    collections::{BTreeMap, HashSet},
+    fmt::Display,
     future::Future,
+    mem,
     path::{Path, PathBuf},
     result,
-    sync::Arc,
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc, Mutex,
+    },
+    time::{Duration, SystemTime},
 };
 use tokio::{
     fs::File,
+    io::AsyncReadExt,
     sync::Semaphore,
     task::{self, JoinHandle},
 };
@@ -28,6 +46,14 @@ use tokio_util::task::TaskTracker;
 use uuid::Uuid;
 
 const DATABASE_DUMP_FILENAME: &str = "fstore.dump";
+const MAX_FILENAME_LENGTH: usize = 255;
+const MAX_KEY_LENGTH: usize = 1024;
+const MAX_METADATA_SIZE: usize = 8 * 1024;
+const DEFAULT_PAGE_SIZE: u32 = 1000;
+const MAX_PAGE_SIZE: u32 = 10_000;
+const OBJECT_STREAM_BUFFER: usize = 64;
+const RECLAIM_BATCH_SIZE: usize = 1000;
+const RECLAIM_GRACE_PERIOD: Duration = Duration::from_secs(10 * 60);
 const DEFAULT_SQL_DIRECTORY: &str =
     match option_env!("FSTORE_DEFAULT_SQL_DIRECTORY") {
         Some(dir) => dir,
@@ -64,6 +90,178 @@ impl DatabaseConfig {
     }
 }
 
+/// What `check` does with entries in the objects directory that do not
+/// belong there.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum OrphanAction {
+    /// Only report orphaned entries.
+    #[default]
+    Report,
+
+    /// Move orphaned entries into the quarantine directory.
+    Quarantine,
+
+    /// Delete orphaned entries.
+    Delete,
+}
+
+#[derive(Debug, Default)]
+pub struct OrphanReport {
+    /// Object files with no database record and unexpected directory
+    /// entries.
+    pub orphans: Vec<Orphan>,
+
+    /// The directory orphans were moved to, if they were quarantined.
+    pub quarantine: Option<PathBuf>,
+}
+
+/// Encodes the position of an object within a bucket's listing.
+fn encode_cursor(object: &Object) -> String {
+    format!("{}_{}", object.added.timestamp_micros(), object.id)
+}
+
+fn decode_cursor(cursor: &str) -> Result<(db::Timestamp, Uuid)> {
+    let (micros, id) = cursor
+        .split_once('_')
+        .ok_or(Error::InvalidCursor("missing separator"))?;
+
+    let added = micros
+        .parse()
+        .ok()
+        .and_then(DateTime::from_timestamp_micros)
+        .ok_or(Error::InvalidCursor("invalid timestamp"))?;
+    let id =
+        Uuid::try_parse(id).map_err(|_| Error::InvalidCursor("invalid ID"))?;
+
+    Ok((added.with_timezone(&Local), id))
+}
+
+/// Checks that a database dump exists and can be read, without loading it.
+async fn check_dump(path: &Path) -> Result<()> {
+    let invalid = |message: String| {
+        Error::Internal(format!("Database dump '{}' {message}", path.display()))
+    };
+
+    let mut file = File::open(path)
+        .await
+        .map_err(|err| invalid(format!("cannot be opened: {err}")))?;
+
+    let metadata = file
+        .metadata()
+        .await
+        .map_err(|err| invalid(format!("cannot be read: {err}")))?;
+
+    if !metadata.is_file() {
+        return Err(invalid("is not a regular file".into()));
+    }
+
+    let mut buf = [0; 1];
+    let read = file
+        .read(&mut buf)
+        .await
+        .map_err(|err| invalid(format!("cannot be read: {err}")))?;
+
+    if read == 0 {
+        return Err(invalid("is empty".into()));
+    }
+
+    Ok(())
+}
+
+fn validate_filename(filename: &str) -> Result<()> {
+    if filename.is_empty() {
+        Err(Error::InvalidFilename("filename must not be empty"))
+    } else if filename.len() > MAX_FILENAME_LENGTH {
+        Err(Error::InvalidFilename("filename must not exceed 255 bytes"))
+    } else if filename.contains(['/', '\\']) {
+        Err(Error::InvalidFilename("filename must not contain a path"))
+    } else if filename.contains(char::is_control) {
+        Err(Error::InvalidFilename(
+            "filename must not contain control characters",
+        ))
+    } else {
+        Ok(())
+    }
+}
+
+fn validate_key(key: &str) -> Result<()> {
+    if key.is_empty() {
+        Err(Error::InvalidKey("key must not be empty"))
+    } else if key.len() > MAX_KEY_LENGTH {
+        Err(Error::InvalidKey("key must not exceed 1024 bytes"))
+    } else if key.contains('\0') {
+        Err(Error::InvalidKey("key must not contain NUL characters"))
+    } else if key
+        .split('/')
+        .any(|segment| matches!(segment, "" | "." | ".."))
+    {
+        Err(Error::InvalidKey(
+            "key segments must not be empty, '.' or '..'",
+        ))
+    } else {
+        Ok(())
+    }
+}
+
+/// Returns metadata entries with their names lowercased, as metadata is
+/// also sent in HTTP headers, whose names are case-insensitive.
+///
+/// The size limit is also enforced by the database on merged metadata.
+fn validate_metadata<V>(
+    entries: &BTreeMap<String, V>,
+    value_len: fn(&V) -> usize,
+) -> Result<BTreeMap<String, V>>
+where
+    V: Clone,
+{
+    let mut normalized = BTreeMap::new();
+    let mut size = 0;
+
+    for (name, value) in entries {
+        if name.is_empty() {
+            return Err(Error::InvalidMetadata("names must not be empty"));
+        }
+
+        size += name.len() + value_len(value);
+
+        if normalized
+            .insert(name.to_lowercase(), value.clone())
+            .is_some()
+        {
+            return Err(Error::InvalidMetadata(
+                "names must be unique regardless of case",
+            ));
+        }
+    }
+
+    if size > MAX_METADATA_SIZE {
+        return Err(Error::InvalidMetadata("metadata must not exceed 8 KiB"));
+    }
+
+    Ok(normalized)
+}
+
+/// Label names appear in selectors such as `owner=alice,tier`, so they may
+/// not contain the characters used to separate entries.
+fn validate_labels<'a, I>(names: I) -> Result<()>
+where
+    I: IntoIterator<Item = &'a String>,
+{
+    for name in names {
+        if name.is_empty() {
+            return Err(Error::InvalidLabel("names must not be empty"));
+        }
+
+        if name.contains([',', '=']) {
+            return Err(Error::InvalidLabel(
+                "names must not contain ',' or '='",
+            ));
+        }
+    }
+
+    Ok(())
+}
+
 #[derive(Clone, Copy, Debug)]
 pub struct StoreOptions<'a> {
     pub version: Version,
@@ -96,6 +294,79 @@ impl ObjectStreamAction for CheckAction {
     }
 }
 
+#[derive(Clone, Debug)]
+struct RepairAction {
+    archive: Arc<PathBuf>,
+    failed: Arc<Mutex<Vec<ObjectError>>>,
+}
+
+impl RepairAction {
+    fn new(path: &Path) -> Self {
+        Self {
+            archive: Arc::new(path.to_owned()),
+            failed: Default::default(),
+        }
+    }
+}
+
+impl ObjectStreamAction for RepairAction {
+    async fn run(
+        &self,
+        store: &ObjectStore,
+        object: &db::Object,
+    ) -> result::Result<(), String> {
+        let result = store
+            .filesystem
+            .restore(&object.object_id, self.archive.as_path(), &object.hash)
+            .await;
+
+        match result {
+            Ok(_) => Ok(()),
+            Err(message) => {
+                self.failed.lock().unwrap().push(ObjectError {
+                    object_id: object.object_id,
+                    message: message.clone(),
+                });
+                Err(message)
+            }
+        }
+    }
+}
+
+#[derive(Clone, Debug)]
+struct RestoreAction {
+    archive: Arc<PathBuf>,
+    restored: Arc<AtomicU64>,
+}
+
+impl RestoreAction {
+    fn new(path: &Path) -> Self {
+        Self {
+            archive: Arc::new(path.to_owned()),
+            restored: Default::default(),
+        }
+    }
+}
+
+impl ObjectStreamAction for RestoreAction {
+    async fn run(
+        &self,
+        store: &ObjectStore,
+        object: &db::Object,
+    ) -> result::Result<(), String> {
+        let restored = store
+            .filesystem
+            .restore(&object.object_id, self.archive.as_path(), &object.hash)
+            .await?;
+
+        if restored {
+            self.restored.fetch_add(1, Ordering::Relaxed);
+        }
+
+        Ok(())
+    }
+}
+
 #[derive(Clone, Debug)]
 struct SyncAction {
     archive: Arc<PathBuf>,
@@ -126,6 +397,8 @@ impl ObjectStreamAction for SyncAction {
 pub struct Tasks {
     pub archive: Task,
     pub check: Task,
+    pub repair: Task,
+    pub restore: Task,
 }
 
 pub struct ObjectStore {
@@ -207,9 +480,12 @@ impl ObjectStore {
         Ok((progress, handle))
     }
 
+    /// Verifies the hashes of all object files, then looks for files and
+    /// directories in the objects directory that do not belong there.
     pub async fn check(
         self: Arc<Self>,
-    ) -> Result<(Progress, JoinHandle<Result<()>>)> {
+        orphans: OrphanAction,
+    ) -> Result<(Progress, JoinHandle<Result<OrphanReport>>)> {
         let started = Local::now();
         let total = self.get_object_count(started).await?;
         let guard =
@@ -218,7 +494,8 @@ impl ObjectStore {
         let progress = guard.clone();
 
         let handle = task::spawn(async move {
-            self.for_each_object(guard, CheckAction).await
+            self.clone().for_each_object(guard, CheckAction).await?;
+            self.check_orphans(orphans).await
         });
 
         Ok((progress, handle))
@@ -236,8 +513,81 @@ impl ObjectStore {
         self.db_support.reset().await
     }
 
-    pub async fn restore(&self, path: &Path) -> result::Result<(), String> {
-        self.db_support.restore(path).await
+    /// Replaces the files of objects with recorded errors by their archived
+    /// copies.
+    ///
+    /// An object's error is cleared once its file is repaired. The returned
+    /// task resolves to the errors of objects that could not be repaired.
+    pub async fn repair(
+        self: Arc<Self>,
+    ) -> Result<(Progress, JoinHandle<Result<Vec<ObjectError>>>)> {
+        let archive = self.archive.as_deref().ok_or_else(|| {
+            Error::Internal("archive location not specified".into())
+        })?;
+
+        let objects = self.database.get_errored_objects().await?;
+        let started = Local::now();
+        let guard = ProgressGuard::new(
+            started,
+            objects.len() as u64,
+            self.tasks.repair.clone(),
+        )?;
+
+        let progress = guard.clone();
+        let action = RepairAction::new(archive);
+        let failed = action.failed.clone();
+
+        let handle = task::spawn(async move {
+            let stream = stream::iter(objects.into_iter().map(Ok::<_, Error>));
+            self.process_objects(stream, guard, action).await?;
+
+            let mut failed = failed.lock().unwrap();
+            Ok(mem::take(&mut *failed))
+        });
+
+        Ok((progress, handle))
+    }
+
+    /// Restores the database and object files from an archive.
+    ///
+    /// Object files that are missing from the home directory or fail their
+    /// hash check are copied from the archive. In a dry run, nothing is
+    /// changed: the archive's database dump is checked for readability
+    /// without being loaded, and each archived object file is read and
+    /// compared against its home copy. The returned task resolves to the
+    /// number of object files restored, or that would be restored.
+    pub async fn restore(
+        self: Arc<Self>,
+        archive: &Path,
+        dry_run: bool,
+    ) -> Result<(Progress, JoinHandle<Result<u64>>)> {
+        let dump = archive.join(DATABASE_DUMP_FILENAME);
+
+        if dry_run {
+            check_dump(&dump).await?;
+            return self.check_archive(archive).await;
+        }
+
+        self.db_support
+            .restore(&dump)
+            .await
+            .map_err(Error::Internal)?;
+
+        let started = Local::now();
+        let total = self.get_object_count(started).await?;
+        let guard =
+            ProgressGuard::new(started, total, self.tasks.restore.clone())?;
+
+        let progress = guard.clone();
+        let action = RestoreAction::new(archive);
+        let restored = action.restored.clone();
+
+        let handle = task::spawn(async move {
+            self.for_each_object(guard, action).await?;
+            Ok(restored.load(Ordering::Relaxed))
+        });
+
+        Ok((progress, handle))
     }
 
     pub fn about(&self) -> &About {
@@ -248,6 +598,15 @@ impl ObjectStore {
         Ok(self.database.create_bucket(name).await?.into())
     }
 
+    /// Returns the token whose secret is `secret`, if there is one.
+    pub async fn authenticate(&self, secret: &str) -> Result<Option<Token>> {
+        Ok(self
+            .database
+            .get_token(&token::hash(secret)?)
+            .await?
+            .map(Into::into))
+    }
+
     pub async fn clone_bucket(
         &self,
         original: Uuid,
@@ -256,14 +615,40 @@ impl ObjectStore {
         Ok(self.database.clone_bucket(original, name).await?.into())
     }
 
+    #[allow(clippy::too_many_arguments)]
     pub async fn commit_part(
         &self,
         bucket_id: &Uuid,
         part_id: &Uuid,
+        expected_hash: Option<&str>,
+        expected_size: Option<u64>,
+        key: Option<&str>,
+        user_metadata: &Metadata,
+        filename: Option<&str>,
     ) -> Result<Object> {
-        let metadata = self.filesystem.commit(part_id).await?;
+        if let Some(key) = key {
+            validate_key(key)?;
+        }
 
-        Ok(self
+        if let Some(filename) = filename {
+            validate_filename(filename)?;
+        }
+
+        let user_metadata = validate_metadata(user_metadata, String::len)?;
+
+        // Check the bucket first, so that a missing bucket does not leave
+        // the committed file without a database record.
+        self.database
+            .fetch_bucket_by_id(bucket_id)
+            .await?
+            .ok_or_not_found("Bucket")?;
+
+        let metadata = self
+            .filesystem
+            .commit(part_id, expected_hash, expected_size)
+            .await?;
+
+        let object: Object = match self
             .database
             .add_object(
                 bucket_id,
@@ -272,9 +657,62 @@ impl ObjectStore {
                 metadata.size.try_into().unwrap(),
                 metadata.r#type.as_str(),
                 metadata.subtype.as_str(),
+                key,
+                Json(&user_metadata),
+                filename,
             )
-            .await?
-            .into())
+            .await
+        {
+            Ok(object) => object.into(),
+            Err(err) => {
+                // The committed file has no database record to refer to it.
+                if let Err(remove_err) = self
+                    .filesystem
+                    .remove_objects([&metadata.id].into_iter())
+                    .await
+                {
+                    error!(
+                        "Failed to remove object file ({}) after the \
+                        object could not be added: {remove_err}",
+                        metadata.id
+                    );
+                }
+
+                return Err(err.into());
+            }
+        };
+
+        if object.id != metadata.id {
+            debug!(
+                "Part ({}) has the same contents as object ({}): \
+                removing duplicate file",
+                metadata.id, object.id
+            );
+
+            self.filesystem
+                .remove_objects([&metadata.id].into_iter())
+                .await?;
+        }
+
+        Ok(object)
+    }
+
+    /// Creates an API token, returning it along with its secret.
+    ///
+    /// Only a hash of the secret is stored, so it cannot be retrieved later.
+    pub async fn create_token(
+        &self,
+        name: &str,
+        scope: Scope,
+        buckets: Option<&[Uuid]>,
+    ) -> Result<(Token, String)> {
+        let secret = token::generate()?;
+        let token = self
+            .database
+            .create_token(name, &token::hash(&secret)?, scope.as_str(), buckets)
+            .await?;
+
+        Ok((token.into(), secret))
     }
 
     pub async fn get_all_objects(
@@ -294,10 +732,44 @@ impl ObjectStore {
         Ok(self.database.fetch_bucket(name).await?.into())
     }
 
-    pub async fn get_buckets(&self) -> Result<Vec<Bucket>> {
+    /// Returns the objects in a bucket with their keys, metadata and
+    /// filenames, or only the objects listed in `objects`.
+    ///
+    /// Fails if a listed object is not in the bucket.
+    pub async fn get_bucket_contents(
+        &self,
+        bucket_id: Uuid,
+        objects: Option<&[Uuid]>,
+    ) -> Result<Vec<Object>> {
+        let contents: Vec<Object> = self
+            .database
+            .get_bucket_contents(bucket_id, objects)
+            .await?
+            .into_iter()
+            .map(Into::into)
+            .collect();
+
+        if let Some(objects) = objects {
+            let found: HashSet<_> =
+                contents.iter().map(|object| object.id).collect();
+
+            if !objects.iter().all(|id| found.contains(id)) {
+                return Err(Error::NotFound("Object"));
+            }
+        }
+
+        Ok(contents)
+    }
+
+    /// Returns the buckets whose labels match `selector`. An empty selector
+    /// matches every bucket.
+    pub async fn get_buckets(
+        &self,
+        selector: &LabelSelector,
+    ) -> Result<Vec<Bucket>> {
         Ok(self
             .database
-            .fetch_buckets_all()
+            .fetch_buckets_all(Json(&selector.labels), &selector.names)
             .await?
             .into_iter()
             .map(|bucket| bucket.into())
@@ -308,6 +780,26 @@ impl ObjectStore {
         self.filesystem.object(object_id).await
     }
 
+    pub async fn get_object_by_hash(&self, hash: &str) -> Result<Object> {
+        self.database
+            .get_object_by_hash(&hash.to_ascii_lowercase())
+            .await?
+            .map(Into::into)
+            .ok_or_not_found("Object")
+    }
+
+    pub async fn get_object_by_key(
+        &self,
+        bucket_id: Uuid,
+        key: &str,
+    ) -> Result<Object> {
+        self.database
+            .get_object_by_key(bucket_id, key)
+            .await?
+            .map(Into::into)
+            .ok_or_not_found("Key")
+    }
+
     pub async fn get_object_errors(&self) -> Result<Vec<ObjectError>> {
         Ok(self
             .database
@@ -329,6 +821,88 @@ impl ObjectStore {
             .ok_or_not_found("Bucket or object not found")
     }
 
+    /// Lists the keyed objects in a bucket whose keys begin with `prefix`.
+    ///
+    /// If a delimiter is given, keys containing it after the prefix are
+    /// grouped into common prefixes instead of being listed.
+    pub async fn get_keys(
+        &self,
+        bucket_id: Uuid,
+        prefix: &str,
+        delimiter: Option<&str>,
+    ) -> Result<KeyList> {
+        let objects = self
+            .database
+            .get_keyed_objects(bucket_id, prefix, delimiter)
+            .await?
+            .into_iter()
+            .map(Into::into)
+            .collect();
+
+        let prefixes = match delimiter {
+            Some(delimiter) => {
+                self.database
+                    .get_key_prefixes(bucket_id, prefix, delimiter)
+                    .await?
+            }
+            None => Vec::new(),
+        };
+
+        Ok(KeyList { objects, prefixes })
+    }
+
+    /// Returns the objects in a bucket matching `query`, one page at a time.
+    pub async fn get_object_page(
+        &self,
+        bucket_id: Uuid,
+        query: &ObjectQuery,
+    ) -> Result<ObjectPage> {
+        let (after_added, after_id) = match query.after.as_deref() {
+            Some(cursor) => {
+                let (added, id) = decode_cursor(cursor)?;
+                (Some(added), Some(id))
+            }
+            None => (None, None),
+        };
+
+        let limit = query
+            .limit
+            .unwrap_or(DEFAULT_PAGE_SIZE)
+            .clamp(1, MAX_PAGE_SIZE) as usize;
+        let size = |size: Option<u64>| {
+            size.map(|size| i64::try_from(size).unwrap_or(i64::MAX))
+        };
+
+        // Fetch one extra object to find out whether another page follows.
+        let mut objects: Vec<Object> = self
+            .database
+            .get_bucket_objects_page(
+                bucket_id,
+                after_added,
+                after_id,
+                limit as i64 + 1,
+                query.r#type.as_deref(),
+                query.subtype.as_deref(),
+                size(query.min_size),
+                size(query.max_size),
+                query.added_after,
+                query.added_before,
+            )
+            .await?
+            .into_iter()
+            .map(Into::into)
+            .collect();
+
+        let next = if objects.len() > limit {
+            objects.truncate(limit);
+            objects.last().map(encode_cursor)
+        } else {
+            None
+        };
+
+        Ok(ObjectPage { objects, next })
+    }
+
     pub async fn get_objects(
         &self,
         bucket_id: Uuid,
@@ -358,10 +932,98 @@ impl ObjectStore {
         self.filesystem.part(id).await
     }
 
+    pub async fn get_part_info(&self, part_id: &Uuid) -> Result<fstore::Part> {
+        Ok(self.filesystem.part_info(part_id).await?.into())
+    }
+
+    pub async fn get_part_ranges(
+        &self,
+        part_id: &Uuid,
+    ) -> Result<Vec<PartRange>> {
+        Ok(self
+            .filesystem
+            .part_ranges(part_id)
+            .await?
+            .into_iter()
+            .map(|range| PartRange {
+                start: range.start,
+                end: range.end,
+            })
+            .collect())
+    }
+
+    /// Returns all uploads that have not yet been committed.
+    pub async fn get_parts(&self) -> Result<Vec<fstore::Part>> {
+        let mut parts: Vec<fstore::Part> = self
+            .filesystem
+            .parts()
+            .await?
+            .into_iter()
+            .map(Into::into)
+            .collect();
+
+        parts.sort_by_key(|part| part.modified);
+        Ok(parts)
+    }
+
+    /// Opens a part for writing at explicit offsets, creating it if needed.
+    pub async fn get_ranged_part(&self, part_id: &Uuid) -> Result<RangedPart> {
+        self.filesystem.ranged_part(part_id).await
+    }
+
+    /// Returns the user-defined metadata of an object within a bucket.
+    pub async fn get_user_metadata(
+        &self,
+        bucket_id: Uuid,
+        object_id: Uuid,
+    ) -> Result<Metadata> {
+        self.database
+            .get_bucket_object(bucket_id, object_id)
+            .await?
+            .map(|object| Object::from(object).metadata)
+            .ok_or_not_found("Bucket or object")
+    }
+
+    pub async fn get_tokens(&self) -> Result<Vec<Token>> {
+        Ok(self
+            .database
+            .get_tokens()
+            .await?
+            .into_iter()
+            .map(Into::into)
+            .collect())
+    }
+
     pub async fn get_totals(&self) -> Result<StoreTotals> {
         Ok(self.database.fetch_store_totals().await?.into())
     }
 
+    /// Adds an object that is already in the store to a bucket.
+    pub async fn link_object(
+        &self,
+        bucket_id: &Uuid,
+        object_id: &Uuid,
+    ) -> Result<Object> {
+        self.database
+            .link_object(bucket_id, object_id)
+            .await?
+            .map(Into::into)
+            .ok_or_not_found("Bucket or object")
+    }
+
+    /// Adds the object with the given SHA-256 hash to a bucket.
+    pub async fn link_object_by_hash(
+        &self,
+        bucket_id: &Uuid,
+        hash: &str,
+    ) -> Result<Object> {
+        self.database
+            .link_object_by_hash(bucket_id, &hash.to_ascii_lowercase())
+            .await?
+            .map(Into::into)
+            .ok_or_not_found("Bucket or object")
+    }
+
     pub async fn prune(&self) -> Result<Vec<Object>> {
         let mut tx = self.database.begin().await?;
         let objects = tx.remove_orphan_objects().await?;
@@ -384,10 +1046,100 @@ impl ObjectStore {
         Ok(objects.into_iter().map(|obj| obj.into()).collect())
     }
 
+    /// Removes object files that have no corresponding database record.
+    ///
+    /// Files changed within the last few minutes are left alone so that
+    /// parts which are in the middle of being committed are not removed.
+    pub async fn reclaim(&self) -> Result<RemoveResult> {
+        let before = SystemTime::now() - RECLAIM_GRACE_PERIOD;
+        let files = self.filesystem.object_files(before).await?;
+        let mut result = RemoveResult::default();
+
+        for batch in files.chunks(RECLAIM_BATCH_SIZE) {
+            let ids: Vec<Uuid> = batch.iter().map(|file| file.id).collect();
+            let missing: HashSet<Uuid> = self
+                .database
+                .get_missing_objects(&ids)
+                .await?
+                .into_iter()
+                .collect();
+
+            let orphans: Vec<_> = batch
+                .iter()
+                .filter(|file| missing.contains(&file.id))
+                .collect();
+
+            self.filesystem
+                .remove_objects(orphans.iter().map(|file| &file.id))
+                .await?;
+
+            result.objects_removed += orphans.len() as u64;
+            result.space_freed +=
+                orphans.iter().map(|file| file.size).sum::<u64>();
+        }
+
+        info!(
+            "Reclaimed {} object file{}",
+            result.objects_removed,
+            match result.objects_removed {
+                1 => "",
+                _ => "s",
+            }
+        );
+
+        Ok(result)
+    }
+
     pub async fn remove_bucket(&self, bucket_id: &Uuid) -> Result<()> {
         Ok(self.database.remove_bucket(bucket_id).await?)
     }
 
+    /// Deletes uploads that have not been written to within `ttl`, along
+    /// with hash state and range files left behind without a part.
+    ///
+    /// Parts that are currently being written to or committed are skipped.
+    pub async fn remove_expired_parts(
+        &self,
+        ttl: Duration,
+    ) -> Result<Vec<fstore::Part>> {
+        let before = SystemTime::now() - ttl;
+        let mut removed = Vec::new();
+
+        for part in self.filesystem.parts().await? {
+            if part.modified >= before {
+                continue;
+            }
+
+            match self.filesystem.remove_part(&part.id, Some(before)).await {
+                Ok(true) => {
+                    debug!("Removed expired part {}", part.id);
+                    removed.push(part.into());
+                }
+                Ok(false) | Err(Error::WriteLock) | Err(Error::NotFound(_)) => {
+                    trace!("Skipping part {}: in use or removed", part.id)
+                }
+                Err(err) => return Err(err),
+            }
+        }
+
+        for path in self.filesystem.remove_stale_sidecars(before).await? {
+            debug!("Removed stale part file '{}'", path.display());
+        }
+
+        if !removed.is_empty() {
+            info!(
+                "Removed {} expired part{}",
+                removed.len(),
+                match removed.len() {
+                    1 => "",
+                    _ => "s",
+                }
+            );
+        }
+
+        Ok(removed)
+    }
+
     pub async fn remove_object(
         &self,
         bucket_id: &Uuid,
@@ -400,6 +1152,19 @@ impl ObjectStore {
             .ok_or_not_found("Bucket or object not found")
     }
 
+    /// Removes the object with the given key from a bucket.
+    pub async fn remove_object_by_key(
+        &self,
+        bucket_id: Uuid,
+        key: &str,
+    ) -> Result<Object> {
+        self.database
+            .remove_object_by_key(bucket_id, key)
+            .await?
+            .map(Into::into)
+            .ok_or_not_found("Key")
+    }
+
     pub async fn remove_objects(
         &self,
         bucket_id: &Uuid,
@@ -412,6 +1177,12 @@ impl ObjectStore {
             .into())
     }
 
+    /// Deletes an upload that has not been committed.
+    pub async fn remove_part(&self, part_id: &Uuid) -> Result<()> {
+        self.filesystem.remove_part(part_id, None).await?;
+        Ok(())
+    }
+
     pub async fn rename_bucket(
         &self,
         bucket_id: &Uuid,
@@ -420,10 +1191,235 @@ impl ObjectStore {
         Ok(self.database.rename_bucket(bucket_id, new_name).await?)
     }
 
+    /// Deletes an API token so that it can no longer be used.
+    pub async fn revoke_token(&self, token_id: &Uuid) -> Result<Token> {
+        self.database
+            .remove_token(token_id)
+            .await?
+            .map(Into::into)
+            .ok_or_not_found("Token")
+    }
+
     pub async fn shutdown(&self) {
         self.database.close().await
     }
 
+    /// Streams every object in a bucket in the order they were added.
+    ///
+    /// Objects are read from the database as the stream is consumed. The
+    /// stream ends early with an error if the database query fails.
+    pub fn stream_bucket_objects(
+        self: Arc<Self>,
+        bucket_id: Uuid,
+    ) -> impl Stream<Item = Result<Object>> + Send + 'static {
+        let (mut tx, rx) = mpsc::channel(OBJECT_STREAM_BUFFER);
+
+        task::spawn(async move {
+            let stream = self.database.stream_bucket_objects(bucket_id);
+            pin_mut!(stream);
+
+            while let Some(object) = stream.next().await {
+                let object = object.map(Into::into).map_err(Error::from);
+                let failed = object.is_err();
+
+                if tx.send(object).await.is_err() || failed {
+                    break;
+                }
+            }
+        });
+
+        rx
+    }
+
+    /// Changes a bucket's description and labels, returning the updated
+    /// bucket.
+    pub async fn update_bucket(
+        &self,
+        bucket_id: &Uuid,
+        patch: &BucketPatch,
+    ) -> Result<Bucket> {
+        validate_labels(patch.labels.keys())?;
+
+        let mut set = Labels::new();
+        let mut remove = Vec::new();
+
+        for (name, value) in &patch.labels {
+            match value {
+                Some(value) => {
+                    set.insert(name.clone(), value.clone());
+                }
+                None => remove.push(name.clone()),
+            }
+        }
+
+        self.database
+            .update_bucket(
+                bucket_id,
+                patch.description.as_deref(),
+                Json(&set),
+                &remove,
+            )
+            .await?
+            .map(Bucket::from)
+            .ok_or_not_found("Bucket")
+    }
+
+    /// Sets and removes entries in the metadata of an object within a
+    /// bucket, returning the updated metadata.
+    pub async fn update_user_metadata(
+        &self,
+        bucket_id: Uuid,
+        object_id: Uuid,
+        changes: &MetadataPatch,
+    ) -> Result<Metadata> {
+        let changes = validate_metadata(changes, |value| {
+            value.as_ref().map_or(0, String::len)
+        })?;
+
+        let mut set = Metadata::new();
+        let mut remove = Vec::new();
+
+        for (name, value) in changes {
+            match value {
+                Some(value) => {
+                    set.insert(name, value);
+                }
+                None => remove.push(name),
+            }
+        }
+
+        self.database
+            .update_object_metadata(bucket_id, object_id, Json(&set), &remove)
+            .await?
+            .map(|object| Object::from(object).metadata)
+            .ok_or_not_found("Bucket or object")
+    }
+
+    /// Reads each object file in an archive, counting those that differ
+    /// from or are missing in the home directory.
+    async fn check_archive(
+        self: Arc<Self>,
+        archive: &Path,
+    ) -> Result<(Progress, JoinHandle<Result<u64>>)> {
+        let started = Local::now();
+        let files = self.filesystem.archived_objects(archive).await?;
+        let guard = ProgressGuard::new(
+            started,
+            files.len() as u64,
+            self.tasks.restore.clone(),
+        )?;
+
+        let progress = guard.clone();
+        let archive = archive.to_owned();
+
+        let handle = task::spawn(async move {
+            let restored = AtomicU64::new(0);
+
+            stream::iter(files)
+                .for_each_concurrent(num_cpus::get(), |file| {
+                    let archive = &archive;
+                    let guard = &guard;
+                    let restored = &restored;
+                    let store = &self;
+
+                    async move {
+                        match store
+                            .filesystem
+                            .check_archived(&file.id, archive)
+                            .await
+                        {
+                            Ok(true) => {
+                                restored.fetch_add(1, Ordering::Relaxed);
+                            }
+                            Ok(false) => (),
+                            Err(message) => {
+                                guard.error(file.id, message);
+                            }
+                        }
+
+                        guard.increment();
+                    }
+                })
+                .await;
+
+            Ok(restored.load(Ordering::Relaxed))
+        });
+
+        Ok((progress, handle))
+    }
+
+    async fn check_orphans(
+        &self,
+        action: OrphanAction,
+    ) -> Result<OrphanReport> {
+        // Files changed within the grace period may belong to commits that
+        // have not yet been recorded in the database.
+        let before = SystemTime::now() - RECLAIM_GRACE_PERIOD;
+        let Scan { files, unexpected } = self.filesystem.scan_objects().await?;
+        let mut report = OrphanReport {
+            orphans: unexpected,
+            quarantine: None,
+        };
+
+        let files: Vec<Uuid> = files
+            .into_iter()
+            .filter(|file| file.changed < before)
+            .map(|file| file.id)
+            .collect();
+
+        for batch in files.chunks(RECLAIM_BATCH_SIZE) {
+            for id in self.database.get_missing_objects(batch).await? {
+                report.orphans.push(self.filesystem.orphan(&id));
+            }
+        }
+
+        for orphan in &report.orphans {
+            debug!(
+                "Orphaned entry '{}': {}",
+                orphan.path.display(),
+                orphan.reason
+            );
+        }
+
+        if report.orphans.is_empty() {
+            return Ok(report);
+        }
+
+        match action {
+            OrphanAction::Report => (),
+            OrphanAction::Quarantine => {
+                let destination =
+                    self.filesystem.quarantine(&report.orphans)?;
+
+                info!(
+                    "Quarantined {} orphaned entr{} in '{}'",
+                    report.orphans.len(),
+                    match report.orphans.len() {
+                        1 => "y",
+                        _ => "ies",
+                    },
+                    destination.display()
+                );
+
+                report.quarantine = Some(destination);
+            }
+            OrphanAction::Delete => {
+                self.filesystem.remove_orphans(&report.orphans).await?;
+
+                info!(
+                    "Deleted {} orphaned entr{}",
+                    report.orphans.len(),
+                    match report.orphans.len() {
+                        1 => "y",
+                        _ => "ies",
+                    }
+                );
+            }
+        }
+
+        Ok(report)
+    }
+
     async fn get_object_count(&self, start: DateTime<Local>) -> Result<u64> {
         let total = self
             .database
@@ -443,10 +1439,24 @@ impl ObjectStore {
         progress: ProgressGuard,
         action: impl ObjectStreamAction,
     ) -> Result<()> {
+        let stream = self.database.stream_objects(progress.started());
+        self.clone().process_objects(stream, progress, action).await
+    }
+
+    async fn process_objects<S, E>(
+        self: Arc<Self>,
+        stream: S,
+        progress: ProgressGuard,
+        action: impl ObjectStreamAction,
+    ) -> Result<()>
+    where
+        S: Stream<Item = result::Result<db::Object, E>>,
+        E: Display,
+    {
         let tracker = TaskTracker::new();
         let semaphore = Arc::new(Semaphore::new(num_cpus::get()));
         let mut error: Option<Error> = None;
-        let mut stream = self.database.stream_objects(progress.started());
+        pin_mut!(stream);
 
         'stream: while let Some(object) = stream.next().await {
             let object = match object {
@@ -0,0 +1,31 @@
+use crate::error::{internal, Result};
+
+use sha2::{Digest, Sha256};
+
+const SECRET_PREFIX: &str = "fstore_";
+const SECRET_SIZE: usize = 32;
+
+fn encode(bytes: &[u8]) -> Result<String> {
+    let mut buffer = [0u8; 64];
+
+    match base16ct::lower::encode_str(bytes, &mut buffer) {
+        Ok(hex) => Ok(String::from(hex)),
+        Err(err) => internal!("Failed to encode token data: {}", err),
+    }
+}
+
+/// Generates a random token secret.
+pub fn generate() -> Result<String> {
+    let mut bytes = [0u8; SECRET_SIZE];
+
+    if let Err(err) = getrandom::getrandom(&mut bytes) {
+        internal!("Failed to generate token secret: {}", err);
+    }
+
+    Ok(format!("{SECRET_PREFIX}{}", encode(&bytes)?))
+}
+
+/// Returns the hash under which a token's secret is stored.
+pub fn hash(secret: &str) -> Result<String> {
+    encode(&Sha256::digest(secret.as_bytes()))
+}
@@ -1,3 +1,4 @@
+use serde::{Deserialize, Serialize};
 use std::fmt::{self, Display, Formatter};
 
 #[derive(Copy, Clone, Debug)]
@@ -8,15 +9,97 @@ pub enum ErrorKind {
     Other,
 }
 
+/// A machine-readable reason for an error response from the server.
+///
+/// Codes are stable across releases, so callers can branch on them rather
+/// than on error messages.
+#[derive(Clone, Copy, Debug, Deserialize, Eq, Hash, PartialEq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ErrorCode {
+    /// A bucket or key with the same name already exists.
+    AlreadyExists,
+
+    /// The request was malformed.
+    BadRequest,
+
+    /// The API token or presigned URL does not permit the request.
+    Forbidden,
+
+    /// Uploaded data did not match the hash the client expected.
+    HashMismatch,
+
+    /// A task of the same kind is already running.
+    InProgress,
+
+    /// The server failed to handle the request.
+    Internal,
+
+    InvalidCursor,
+
+    InvalidFilename,
+
+    InvalidKey,
+
+    InvalidLabel,
+
+    InvalidMetadata,
+
+    /// An upload part is incomplete or does not match the request.
+    InvalidPart,
+
+    /// The requested bucket, object, upload or token does not exist.
+    NotFound,
+
+    /// Presigned URLs are not enabled on the server.
+    PresignDisabled,
+
+    /// The requested byte range is outside the object's contents.
+    RangeNotSatisfiable,
+
+    /// The request requires an API token, or the token is invalid.
+    Unauthorized,
+
+    /// The request body does not have the expected content type.
+    UnsupportedMediaType,
+
+    /// The object is being written to by another request.
+    WriteLocked,
+
+    /// A code introduced by a newer server.
+    #[serde(other)]
+    Unknown,
+}
+
+/// The JSON body of an error response.
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct ErrorBody {
+    pub code: ErrorCode,
+    pub message: String,
+}
+
 #[derive(Debug)]
 pub struct Error {
     message: String,
     kind: ErrorKind,
+    code: Option<ErrorCode>,
 }
 
 impl Error {
     pub fn new(kind: ErrorKind, message: String) -> Self {
-        Self { message, kind }
+        Self {
+            message,
+            kind,
+            code: None,
+        }
+    }
+
+    /// Creates an error from an error response the server sent.
+    pub fn with_code(kind: ErrorKind, body: ErrorBody) -> Self {
+        Self {
+            message: body.message,
+            kind,
+            code: Some(body.code),
+        }
     }
 
     pub fn other(message: String) -> Self {
@@ -26,6 +109,12 @@ impl Error {
     pub fn kind(&self) -> ErrorKind {
         self.kind
     }
+
+    /// Returns the server's error code, if the error came from a response
+    /// that included one.
+    pub fn code(&self) -> Option<ErrorCode> {
+        self.code
+    }
 }
 
 impl Display for Error {
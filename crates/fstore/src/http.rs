@@ -1,31 +1,206 @@
 #[cfg(feature = "axum")]
 mod axum;
+mod byteranges;
+mod transport;
+#[cfg(unix)]
+mod unix;
+mod upload;
+
+pub use upload::{Upload, DEFAULT_CHUNK_SIZE};
+
+use transport::{RequestBuilder, Transport};
 
 use crate::{
     error::{Error, ErrorKind, Result},
-    model, About, Object, ObjectError, RemoveResult, StoreTotals,
+    model, About, ArchiveFormat, BucketPatch, KeyList, LabelSelector, Metadata,
+    MetadataPatch, Object, ObjectError, ObjectPage, ObjectQuery, Part,
+    PartRange, RemoveResult, Signature, StoreTotals,
 };
 
 pub use headers::Range;
 
+use base64ct::{Base64, Encoding};
 use bytes::Bytes;
 use futures_core::{Stream, TryStream};
+use futures_util::stream;
 use headers::HeaderMapExt;
 use mime::{Mime, TEXT_PLAIN_UTF_8};
+use mime2ext::mime2ext;
+use percent_encoding::{
+    percent_decode_str, utf8_percent_encode, AsciiSet, CONTROLS,
+};
 use reqwest::{
-    header::{HeaderMap, CONTENT_TYPE},
-    Body, Method, RequestBuilder, Response, StatusCode, Url,
+    header::{
+        HeaderMap, HeaderName, HeaderValue, AUTHORIZATION, CONTENT_RANGE,
+        CONTENT_TYPE, RANGE,
+    },
+    Body, Certificate, Identity, Method, Response, StatusCode, Url,
 };
+use serde::Deserialize;
 use std::{
+    borrow::Cow,
     error,
     fmt::{self, Display, Write},
     ops::{Bound, RangeBounds},
+    time::Duration,
 };
 use tokio::io::AsyncRead;
 use tokio_stream::StreamExt;
 use tokio_util::io::ReaderStream;
 use uuid::Uuid;
 
+#[cfg(unix)]
+use std::path::PathBuf;
+
+const ADDED: &str = "x-fstore-added";
+const FILENAME: &str = "x-fstore-filename";
+const KEY: &str = "x-fstore-key";
+const METADATA_PREFIX: &str = "x-fstore-meta-";
+const OBJECT_ID: &str = "x-fstore-object-id";
+const PROXY_REQUEST_HEADERS: [&str; 4] =
+    ["if-modified-since", "if-none-match", "if-range", "range"];
+const PROXY_RESPONSE_HEADERS: [&str; 8] = [
+    "accept-ranges",
+    "cache-control",
+    "content-disposition",
+    "content-length",
+    "content-range",
+    "content-type",
+    "etag",
+    "last-modified",
+];
+const REPR_DIGEST: HeaderName = HeaderName::from_static("repr-digest");
+const SHA256_SIZE: usize = 32;
+const SIZE: &str = "x-fstore-size";
+
+/// Characters percent-encoded in header values, matching the server.
+const ENCODED: &AsciiSet = &CONTROLS.add(b'%');
+
+fn repr_digest(hash: &str) -> Result<String> {
+    let bytes = base16ct::mixed::decode_vec(hash).map_err(|err| {
+        Error::new(
+            ErrorKind::Client,
+            format!("invalid SHA-256 hash '{hash}': {err}"),
+        )
+    })?;
+
+    if bytes.len() != SHA256_SIZE {
+        return Err(Error::new(
+            ErrorKind::Client,
+            format!(
+                "invalid SHA-256 hash '{hash}': expected {SHA256_SIZE} bytes, \
+                found {}",
+                bytes.len()
+            ),
+        ));
+    }
+
+    Ok(format!("sha-256=:{}:", Base64::encode_string(&bytes)))
+}
+
+fn header_error(name: &str, message: impl Display) -> Error {
+    Error::other(format!("invalid '{name}' header in response: {message}"))
+}
+
+fn header<'a>(headers: &'a HeaderMap, name: &str) -> Result<&'a str> {
+    headers
+        .get(name)
+        .ok_or_else(|| header_error(name, "header is missing"))?
+        .to_str()
+        .map_err(|err| header_error(name, err))
+}
+
+fn decode_header(name: &str, value: &str) -> Result<String> {
+    percent_decode_str(value)
+        .decode_utf8()
+        .map(Cow::into_owned)
+        .map_err(|err| header_error(name, err))
+}
+
+/// Appends the segments of `key` to `url`.
+///
+/// Keys with segments that URL normalization would remove or collapse are
+/// rejected, rather than silently addressing a different key.
+fn push_key(url: &mut Url, key: &str) -> Result<()> {
+    if key
+        .split('/')
+        .any(|segment| matches!(segment, "" | "." | ".."))
+    {
+        return Err(Error::new(
+            ErrorKind::Client,
+            format!(
+                "invalid key '{key}': segments must not be empty, '.' or '..'"
+            ),
+        ));
+    }
+
+    url.path_segments_mut().unwrap().extend(key.split('/'));
+    Ok(())
+}
+
+/// Reads an object from the headers the server sends with its data.
+fn object_from_headers(headers: &HeaderMap) -> Result<Object> {
+    let id = Uuid::try_parse(header(headers, OBJECT_ID)?)
+        .map_err(|err| header_error(OBJECT_ID, err))?;
+
+    let hash = header(headers, "etag")?
+        .strip_prefix('"')
+        .and_then(|etag| etag.strip_suffix('"'))
+        .ok_or_else(|| header_error("etag", "expected a strong entity tag"))?
+        .to_owned();
+
+    let size = header(headers, "content-length")?
+        .parse()
+        .map_err(|err| header_error("content-length", err))?;
+
+    let media_type = header(headers, "content-type")?
+        .parse::<Mime>()
+        .map_err(|err| header_error("content-type", err))?;
+    let (r#type, subtype) = media_type
+        .essence_str()
+        .split_once('/')
+        .ok_or_else(|| header_error("content-type", "expected a subtype"))?;
+
+    let added = decode_header(ADDED, header(headers, ADDED)?)?
+        .parse()
+        .map_err(|err| header_error(ADDED, err))?;
+
+    let key = match headers.get(KEY) {
+        Some(_) => Some(decode_header(KEY, header(headers, KEY)?)?),
+        None => None,
+    };
+
+    let filename = match headers.get(FILENAME) {
+        Some(_) => Some(decode_header(FILENAME, header(headers, FILENAME)?)?),
+        None => None,
+    };
+
+    let mut metadata = Metadata::new();
+
+    for (name, value) in headers {
+        let Some(name) = name.as_str().strip_prefix(METADATA_PREFIX) else {
+            continue;
+        };
+
+        let header = format!("{METADATA_PREFIX}{name}");
+        let value = value.to_str().map_err(|err| header_error(&header, err))?;
+        metadata.insert(name.to_owned(), decode_header(&header, value)?);
+    }
+
+    Ok(Object {
+        id,
+        hash,
+        size,
+        r#type: r#type.to_owned(),
+        subtype: subtype.to_owned(),
+        extension: mime2ext(media_type.essence_str()).map(str::to_owned),
+        added,
+        key,
+        metadata,
+        filename,
+    })
+}
+
 #[derive(Clone, Copy, Debug)]
 pub enum ProxyMethod {
     Get,
@@ -48,15 +223,54 @@ pub struct ProxyResponse<S> {
     pub stream: S,
 }
 
+/// Optional settings for object uploads.
+#[derive(Clone, Debug, Default)]
+pub struct UploadOptions {
+    /// The expected SHA-256 hash of the object's contents as a hex string.
+    ///
+    /// The server rejects the upload if the received data does not match.
+    pub hash: Option<String>,
+
+    /// User-defined metadata to store with the object in its bucket.
+    ///
+    /// Metadata is sent as request headers, so names are lowercased by the
+    /// server. Entries are merged into any metadata the object already has
+    /// in the bucket.
+    pub metadata: Metadata,
+
+    /// The name of the file the object was uploaded from.
+    ///
+    /// The server suggests this name to clients that download the object
+    /// from the bucket. It replaces any name the object already has in the
+    /// bucket.
+    pub filename: Option<String>,
+
+    /// The expected size of the object's contents in bytes.
+    ///
+    /// The server rejects the upload if it received a different number of
+    /// bytes. Required to commit an [`Upload`] written with
+    /// [`Upload::write_at`], as the server cannot otherwise tell whether
+    /// the final chunk has arrived.
+    pub size: Option<u64>,
+}
+
+#[derive(Debug, Deserialize)]
+struct NewPart {
+    id: Uuid,
+    written: u64,
+}
+
 impl From<reqwest::Error> for Error {
     fn from(error: reqwest::Error) -> Self {
         Self::other(error.to_string())
     }
 }
 
-trait RequestExt {
+trait RequestExt: Sized {
     fn content_type(self, mime: Mime) -> Self;
 
+    fn upload_options(self, options: &UploadOptions) -> Result<Self>;
+
     async fn send_and_check(self) -> Result<Response>;
 }
 
@@ -65,11 +279,46 @@ impl RequestExt for RequestBuilder {
         self.header(CONTENT_TYPE, mime.as_ref())
     }
 
+    fn upload_options(self, options: &UploadOptions) -> Result<Self> {
+        let mut builder = self;
+
+        if let Some(hash) = &options.hash {
+            builder = builder.header(REPR_DIGEST, repr_digest(hash)?);
+        }
+
+        for (name, value) in &options.metadata {
+            let header = format!("{METADATA_PREFIX}{name}");
+            let header =
+                HeaderName::from_bytes(header.as_bytes()).map_err(|err| {
+                    Error::new(
+                        ErrorKind::Client,
+                        format!("invalid metadata name '{name}': {err}"),
+                    )
+                })?;
+            let value = HeaderValue::from_str(value).map_err(|err| {
+                Error::new(
+                    ErrorKind::Client,
+                    format!("invalid value for metadata '{name}': {err}"),
+                )
+            })?;
+
+            builder = builder.header(header, value);
+        }
+
+        if let Some(filename) = &options.filename {
+            let value = utf8_percent_encode(filename, ENCODED).to_string();
+            builder = builder.header(FILENAME, value);
+        }
+
+        if let Some(size) = options.size {
+            builder = builder.header(SIZE, size);
+        }
+
+        Ok(builder)
+    }
+
     async fn send_and_check(self) -> Result<Response> {
-        let response = self
-            .send()
-            .await
-            .map_err(|err| Error::other(format!("Request failed: {err}")))?;
+        let response = self.send().await?;
 
         let status = response.status();
 
@@ -87,26 +336,132 @@ impl RequestExt for RequestBuilder {
             ErrorKind::Other
         };
 
-        match response.text().await {
-            Ok(text) => Err(Error::new(kind, text)),
-            Err(err) => Err(Error::other(format!(
-                "failed to read response body: {err}"
-            ))),
+        let text = response.text().await.map_err(|err| {
+            Error::other(format!("failed to read response body: {err}"))
+        })?;
+
+        // Responses to HEAD requests have no body to describe the error.
+        if text.is_empty() {
+            return Err(Error::new(kind, status.to_string()));
+        }
+
+        match serde_json::from_str(&text) {
+            Ok(body) => Err(Error::with_code(kind, body)),
+            Err(_) => Err(Error::new(kind, text)),
+        }
+    }
+}
+
+/// Creates a [`Client`] with settings beyond its server URL.
+#[derive(Debug)]
+pub struct ClientBuilder {
+    url: Url,
+    builder: reqwest::ClientBuilder,
+    headers: HeaderMap,
+    #[cfg(unix)]
+    socket: Option<PathBuf>,
+}
+
+impl ClientBuilder {
+    /// Authenticates every request with an API token.
+    pub fn token(mut self, token: &str) -> Result<Self> {
+        let mut value = HeaderValue::from_str(&format!("Bearer {token}"))
+            .map_err(|err| {
+                Error::new(ErrorKind::Client, format!("invalid token: {err}"))
+            })?;
+        value.set_sensitive(true);
+
+        self.headers.insert(AUTHORIZATION, value);
+        Ok(self)
+    }
+
+    /// Trusts servers whose certificates are signed by one of the PEM
+    /// encoded CA certificates, in addition to the system's trusted roots.
+    pub fn ca_certificates(mut self, pem: &[u8]) -> Result<Self> {
+        let certificates =
+            Certificate::from_pem_bundle(pem).map_err(|err| {
+                Error::new(
+                    ErrorKind::Client,
+                    format!("invalid CA certificate: {err}"),
+                )
+            })?;
+
+        for certificate in certificates {
+            self.builder = self.builder.add_root_certificate(certificate);
         }
+
+        Ok(self)
+    }
+
+    /// Presents a client certificate to servers that require one.
+    ///
+    /// Both arguments are PEM encoded, and the key must be in PKCS #8
+    /// format.
+    pub fn identity(mut self, certificate: &[u8], key: &[u8]) -> Result<Self> {
+        let identity =
+            Identity::from_pkcs8_pem(certificate, key).map_err(|err| {
+                Error::new(
+                    ErrorKind::Client,
+                    format!("invalid client certificate or key: {err}"),
+                )
+            })?;
+
+        self.builder = self.builder.identity(identity);
+        Ok(self)
+    }
+
+    /// Sends requests over a Unix socket instead of connecting to the URL's
+    /// host. The URL still provides the base path for requests and the
+    /// host for presigned URLs.
+    #[cfg(unix)]
+    pub fn unix_socket<P: Into<PathBuf>>(mut self, path: P) -> Self {
+        self.socket = Some(path.into());
+        self
+    }
+
+    pub fn build(self) -> Result<Client> {
+        let client = self.builder.build().map_err(|err| {
+            Error::other(format!("failed to build HTTP client: {err}"))
+        })?;
+
+        let transport = Transport::new(client, self.headers);
+
+        #[cfg(unix)]
+        let transport = match self.socket {
+            Some(path) => transport.unix_socket(path),
+            None => transport,
+        };
+
+        Ok(Client {
+            client: transport,
+            url: self.url,
+        })
     }
 }
 
 #[derive(Clone, Debug)]
 pub struct Client {
-    client: reqwest::Client,
+    client: Transport,
     url: Url,
 }
 
 impl Client {
     pub fn new(url: &url::Url) -> Self {
         Self {
-            client: reqwest::Client::new(),
+            client: Transport::new(reqwest::Client::new(), HeaderMap::new()),
+            url: url.clone(),
+        }
+    }
+
+    /// Returns a builder for a client with additional settings, such as
+    /// an API token, TLS certificates or a Unix socket.
+    pub fn builder(url: &url::Url) -> ClientBuilder {
+        ClientBuilder {
             url: url.clone(),
+            builder: reqwest::Client::builder(),
+            headers: HeaderMap::new(),
+            #[cfg(unix)]
+            socket: None,
         }
     }
 
@@ -135,21 +490,45 @@ impl Client {
     }
 
     pub async fn add_object<T>(&self, bucket: Uuid, object: T) -> Result<Object>
+    where
+        T: AsyncRead + Send + Sync + 'static,
+    {
+        self.add_object_with(bucket, object, &Default::default())
+            .await
+    }
+
+    pub async fn add_object_with<T>(
+        &self,
+        bucket: Uuid,
+        object: T,
+        options: &UploadOptions,
+    ) -> Result<Object>
     where
         T: AsyncRead + Send + Sync + 'static,
     {
         let stream = ReaderStream::new(object);
-        self.add_object_stream(bucket, stream).await
+        self.add_object_stream_with(bucket, stream, options).await
     }
 
     pub async fn add_object_bytes(
         &self,
         bucket: Uuid,
         object: Bytes,
+    ) -> Result<Object> {
+        self.add_object_bytes_with(bucket, object, &Default::default())
+            .await
+    }
+
+    pub async fn add_object_bytes_with(
+        &self,
+        bucket: Uuid,
+        object: Bytes,
+        options: &UploadOptions,
     ) -> Result<Object> {
         Ok(self
             .client
             .post(self.path(&["bucket", &bucket.to_string()]))
+            .upload_options(options)?
             .body(object)
             .send_and_check()
             .await?
@@ -162,6 +541,21 @@ impl Client {
         bucket: Uuid,
         stream: S,
     ) -> Result<Object>
+    where
+        S: TryStream + Send + Sync + 'static,
+        S::Error: Into<Box<dyn error::Error + Send + Sync>>,
+        Bytes: From<S::Ok>,
+    {
+        self.add_object_stream_with(bucket, stream, &Default::default())
+            .await
+    }
+
+    pub async fn add_object_stream_with<S>(
+        &self,
+        bucket: Uuid,
+        stream: S,
+        options: &UploadOptions,
+    ) -> Result<Object>
     where
         S: TryStream + Send + Sync + 'static,
         S::Error: Into<Box<dyn error::Error + Send + Sync>>,
@@ -170,6 +564,7 @@ impl Client {
         Ok(self
             .client
             .post(self.path(&["bucket", &bucket.to_string()]))
+            .upload_options(options)?
             .body(Body::wrap_stream(stream))
             .send_and_check()
             .await?
@@ -177,6 +572,23 @@ impl Client {
             .await?)
     }
 
+    async fn append_part(&self, part: Uuid, chunk: Bytes) -> Result<u64> {
+        let written = self
+            .client
+            .post(self.path(&["object", &part.to_string()]))
+            .body(chunk)
+            .send_and_check()
+            .await?
+            .text()
+            .await?;
+
+        written.parse().map_err(|err| {
+            Error::other(format!(
+                "server returned invalid byte count '{written}': {err}"
+            ))
+        })
+    }
+
     pub fn bucket(self, id: &Uuid) -> Bucket {
         Bucket::new(self, id)
     }
@@ -195,6 +607,61 @@ impl Client {
             .await?)
     }
 
+    async fn commit_part(
+        &self,
+        bucket: Uuid,
+        part: Uuid,
+        options: &UploadOptions,
+    ) -> Result<Object> {
+        Ok(self
+            .client
+            .put(self.path(&[
+                "bucket",
+                &bucket.to_string(),
+                "part",
+                &part.to_string(),
+            ]))
+            .upload_options(options)?
+            .send_and_check()
+            .await?
+            .json()
+            .await?)
+    }
+
+    /// Returns a stream of an archive containing the objects in a bucket,
+    /// or only the listed `objects` if given.
+    ///
+    /// The archive begins with a `manifest.json` entry describing the
+    /// objects, followed by each object's data in an entry named by
+    /// [`Object::default_filename`].
+    pub async fn export(
+        &self,
+        bucket: Uuid,
+        format: ArchiveFormat,
+        objects: Option<&[Uuid]>,
+    ) -> Result<impl Stream<Item = std::io::Result<Bytes>>> {
+        let mut request = self
+            .client
+            .get(self.path(&["bucket", &bucket.to_string(), "-", "export"]))
+            .query(&[("format", format)]);
+
+        if let Some(objects) = objects {
+            let body = objects
+                .iter()
+                .map(ToString::to_string)
+                .collect::<Vec<_>>()
+                .join("\n");
+
+            request = request.content_type(TEXT_PLAIN_UTF_8).body(body);
+        }
+
+        Ok(request
+            .send_and_check()
+            .await?
+            .bytes_stream()
+            .map(|result| result.map_err(std::io::Error::other)))
+    }
+
     pub async fn get_all_objects(
         &self,
         bucket_id: Uuid,
@@ -221,9 +688,70 @@ impl Client {
     }
 
     pub async fn get_buckets(&self) -> Result<Vec<model::Bucket>> {
+        self.get_buckets_matching(&LabelSelector::default()).await
+    }
+
+    /// Returns the buckets whose labels match `selector`.
+    pub async fn get_buckets_matching(
+        &self,
+        selector: &LabelSelector,
+    ) -> Result<Vec<model::Bucket>> {
+        let mut request = self.client.get(self.path(&["buckets"]));
+
+        if !selector.is_empty() {
+            request = request.query(&[("labels", selector.to_string())]);
+        }
+
+        Ok(request.send_and_check().await?.json().await?)
+    }
+
+    async fn get_key_data(&self, bucket: Uuid, key: &str) -> Result<Response> {
+        self.client
+            .get(self.key_path(bucket, key)?)
+            .send_and_check()
+            .await
+    }
+
+    pub async fn get_key_bytes(
+        &self,
+        bucket: Uuid,
+        key: &str,
+    ) -> Result<Bytes> {
+        Ok(self.get_key_data(bucket, key).await?.bytes().await?)
+    }
+
+    pub async fn get_key_stream(
+        &self,
+        bucket: Uuid,
+        key: &str,
+    ) -> Result<impl Stream<Item = std::io::Result<Bytes>>> {
+        Ok(self
+            .get_key_data(bucket, key)
+            .await?
+            .bytes_stream()
+            .map(|result| result.map_err(std::io::Error::other)))
+    }
+
+    /// Lists the keyed objects in a bucket whose keys begin with `prefix`.
+    ///
+    /// If a delimiter is given, keys containing it after the prefix are
+    /// rolled up into the returned common prefixes.
+    pub async fn get_keys(
+        &self,
+        bucket: Uuid,
+        prefix: &str,
+        delimiter: Option<&str>,
+    ) -> Result<KeyList> {
+        let mut query = vec![("prefix", prefix)];
+
+        if let Some(delimiter) = delimiter {
+            query.push(("delimiter", delimiter));
+        }
+
         Ok(self
             .client
-            .get(self.path(&["buckets"]))
+            .get(self.path(&["bucket", &bucket.to_string(), "-", "keys"]))
+            .query(&query)
             .send_and_check()
             .await?
             .json()
@@ -248,6 +776,50 @@ impl Client {
             .await?)
     }
 
+    pub async fn get_object_page(
+        &self,
+        bucket: Uuid,
+        query: &ObjectQuery,
+    ) -> Result<ObjectPage> {
+        Ok(self
+            .client
+            .get(self.path(&["bucket", &bucket.to_string(), "objects"]))
+            .query(query)
+            .send_and_check()
+            .await?
+            .json()
+            .await?)
+    }
+
+    /// Returns the pages of objects in a bucket matching `query`.
+    ///
+    /// Each page is requested only once the previous one has been consumed,
+    /// starting from `query.after` if set.
+    pub fn get_object_pages(
+        &self,
+        bucket: Uuid,
+        query: ObjectQuery,
+    ) -> impl Stream<Item = Result<ObjectPage>> {
+        let client = self.clone();
+
+        stream::try_unfold(Some(query), move |query| {
+            let client = client.clone();
+
+            async move {
+                let Some(mut query) = query else {
+                    return Ok(None);
+                };
+
+                let page = client.get_object_page(bucket, &query).await?;
+
+                query.after.clone_from(&page.next);
+                let next = page.next.is_some().then_some(query);
+
+                Ok(Some((page, next)))
+            }
+        })
+    }
+
     pub async fn get_objects(
         &self,
         bucket: Uuid,
@@ -274,6 +846,16 @@ impl Client {
             .await?)
     }
 
+    pub async fn get_object_by_hash(&self, hash: &str) -> Result<Object> {
+        Ok(self
+            .client
+            .get(self.path(&["object", "hash", hash]))
+            .send_and_check()
+            .await?
+            .json()
+            .await?)
+    }
+
     async fn get_object_data(
         &self,
         bucket: Uuid,
@@ -326,6 +908,94 @@ impl Client {
             .await?)
     }
 
+    /// Returns several ranges of an object's data, in the order requested.
+    ///
+    /// The ranges are requested together, and the server sends those it
+    /// can satisfy in a single `multipart/byteranges` response. Ranges
+    /// extending past the end of the object are truncated.
+    pub async fn get_object_bytes_ranges(
+        &self,
+        bucket: Uuid,
+        object: Uuid,
+        ranges: &[std::ops::Range<u64>],
+    ) -> Result<Vec<Bytes>> {
+        if ranges.is_empty() {
+            return Ok(Vec::new());
+        }
+
+        let mut header = String::from("bytes=");
+
+        for (i, range) in ranges.iter().enumerate() {
+            if range.is_empty() {
+                return Err(Error::new(
+                    ErrorKind::Client,
+                    format!("empty range: {range:?}"),
+                ));
+            }
+
+            if i > 0 {
+                header.push(',');
+            }
+
+            write!(header, "{}-{}", range.start, range.end - 1).unwrap();
+        }
+
+        let response = self
+            .client
+            .get(self.path(&[
+                "object",
+                &bucket.to_string(),
+                &object.to_string(),
+                "data",
+            ]))
+            .header(RANGE, header)
+            .send_and_check()
+            .await?;
+
+        let status = response.status();
+        let headers = response.headers();
+
+        let boundary = headers
+            .get(CONTENT_TYPE)
+            .and_then(|value| value.to_str().ok())
+            .and_then(|value| value.parse::<Mime>().ok())
+            .filter(|mime| mime.essence_str() == "multipart/byteranges")
+            .and_then(|mime| {
+                mime.get_param(mime::BOUNDARY).map(|name| name.to_string())
+            });
+        let content_range = headers
+            .get(CONTENT_RANGE)
+            .and_then(|value| value.to_str().ok())
+            .and_then(byteranges::parse_content_range);
+
+        let body = response.bytes().await?;
+
+        let parts = match (status, boundary) {
+            (StatusCode::PARTIAL_CONTENT, Some(boundary)) => {
+                byteranges::parse(&body, &boundary)?
+            }
+            (StatusCode::PARTIAL_CONTENT, None) => {
+                let (start, _) = content_range.ok_or_else(|| {
+                    Error::other(
+                        "partial response without a Content-Range".into(),
+                    )
+                })?;
+
+                vec![byteranges::Part { start, data: body }]
+            }
+            // The server may send the entire object instead.
+            _ => vec![byteranges::Part {
+                start: 0,
+                data: body,
+            }],
+        };
+
+        ranges
+            .iter()
+            .map(|range| byteranges::extract(&parts, range))
+            .collect()
+    }
+
     pub async fn get_object_stream(
         &self,
         bucket: Uuid,
@@ -362,59 +1032,262 @@ impl Client {
             .await?)
     }
 
-    fn path<I>(&self, segments: I) -> Url
-    where
-        I: IntoIterator,
-        I::Item: AsRef<str>,
-    {
-        let mut url = self.url.clone();
-        url.path_segments_mut().unwrap().extend(segments);
-        url
+    pub async fn get_part(&self, id: Uuid) -> Result<Part> {
+        Ok(self
+            .client
+            .get(self.path(&["part", &id.to_string()]))
+            .send_and_check()
+            .await?
+            .json()
+            .await?)
     }
 
-    pub async fn proxy(
-        &self,
-        bucket: Uuid,
-        object: Uuid,
-        method: ProxyMethod,
-        range: Option<Range>,
-    ) -> reqwest::Result<
-        ProxyResponse<impl Stream<Item = std::io::Result<Bytes>>>,
-    > {
-        let method = match method {
-            ProxyMethod::Get => Method::GET,
-            ProxyMethod::Head => Method::HEAD,
-        };
-
-        let url = self.path(&[
-            "object",
-            &bucket.to_string(),
-            &object.to_string(),
-            "data",
-        ]);
+    /// Returns the byte ranges that have been written to a part.
+    pub async fn get_part_ranges(&self, id: Uuid) -> Result<Vec<PartRange>> {
+        Ok(self
+            .client
+            .get(self.path(&["part", &id.to_string(), "ranges"]))
+            .send_and_check()
+            .await?
+            .json()
+            .await?)
+    }
+
+    /// Returns all uploads that have not yet been committed.
+    pub async fn get_parts(&self) -> Result<Vec<Part>> {
+        Ok(self
+            .client
+            .get(self.path(&["parts"]))
+            .send_and_check()
+            .await?
+            .json()
+            .await?)
+    }
+
+    fn key_path(&self, bucket: Uuid, key: &str) -> Result<Url> {
+        let mut url = self.path(&["bucket", &bucket.to_string(), "key"]);
+        push_key(&mut url, key)?;
+        Ok(url)
+    }
+
+    /// Returns the user-defined metadata of an object within a bucket.
+    pub async fn get_user_metadata(
+        &self,
+        bucket: Uuid,
+        object: Uuid,
+    ) -> Result<Metadata> {
+        Ok(self
+            .client
+            .get(self.path(&[
+                "object",
+                &bucket.to_string(),
+                &object.to_string(),
+                "metadata",
+            ]))
+            .send_and_check()
+            .await?
+            .json()
+            .await?)
+    }
+
+    /// Returns an object within a bucket from the headers of a HEAD
+    /// request for its data, without transferring its contents.
+    pub async fn head_object(
+        &self,
+        bucket: Uuid,
+        object: Uuid,
+    ) -> Result<Object> {
+        let response = self
+            .client
+            .head(self.path(&[
+                "object",
+                &bucket.to_string(),
+                &object.to_string(),
+                "data",
+            ]))
+            .send_and_check()
+            .await?;
 
+        object_from_headers(response.headers())
+    }
+
+    pub async fn link_object(
+        &self,
+        bucket: Uuid,
+        object: Uuid,
+    ) -> Result<Object> {
+        Ok(self
+            .client
+            .post(self.path(&[
+                "object",
+                &bucket.to_string(),
+                &object.to_string(),
+            ]))
+            .send_and_check()
+            .await?
+            .json()
+            .await?)
+    }
+
+    pub async fn link_object_by_hash(
+        &self,
+        bucket: Uuid,
+        hash: &str,
+    ) -> Result<Object> {
+        Ok(self
+            .client
+            .post(self.path(&["object", &bucket.to_string(), "hash", hash]))
+            .send_and_check()
+            .await?
+            .json()
+            .await?)
+    }
+
+    /// Starts a new chunked upload.
+    pub async fn new_upload(&self) -> Result<Upload> {
+        let part: NewPart = self
+            .client
+            .post(self.path(&["object"]))
+            .send_and_check()
+            .await?
+            .json()
+            .await?;
+
+        Ok(Upload::new(self.clone(), part.id, part.written))
+    }
+
+    fn path<I>(&self, segments: I) -> Url
+    where
+        I: IntoIterator,
+        I::Item: AsRef<str>,
+    {
+        let mut url = self.url.clone();
+        url.path_segments_mut().unwrap().extend(segments);
+        url
+    }
+
+    /// Requests a signature for `url` from the server at `path`, returning
+    /// the presigned URL.
+    async fn presign(
+        &self,
+        path: Url,
+        mut url: Url,
+        expires_in: Option<Duration>,
+    ) -> Result<Url> {
+        let mut request = self.client.post(path);
+
+        if let Some(expires_in) = expires_in {
+            request = request.query(&[("expires_in", expires_in.as_secs())]);
+        }
+
+        let Signature { expires, signature } =
+            request.send_and_check().await?.json().await?;
+
+        url.query_pairs_mut()
+            .append_pair("expires", &expires.to_string())
+            .append_pair("signature", &signature);
+
+        Ok(url)
+    }
+
+    /// Returns a URL that downloads an object's data without an API token.
+    ///
+    /// The URL expires after `expires_in`, which defaults to one hour and is
+    /// limited to seven days by the server.
+    pub async fn presign_get(
+        &self,
+        bucket: Uuid,
+        object: Uuid,
+        expires_in: Option<Duration>,
+    ) -> Result<Url> {
+        let bucket = bucket.to_string();
+        let object = object.to_string();
+
+        self.presign(
+            self.path(&["object", &bucket, &object, "presign"]),
+            self.path(&["object", &bucket, &object, "data"]),
+            expires_in,
+        )
+        .await
+    }
+
+    /// Returns a URL that uploads an object under `key` in a bucket with a
+    /// `PUT` request, without an API token.
+    ///
+    /// The URL expires after `expires_in`, which defaults to one hour and is
+    /// limited to seven days by the server.
+    pub async fn presign_put(
+        &self,
+        bucket: Uuid,
+        key: &str,
+        expires_in: Option<Duration>,
+    ) -> Result<Url> {
+        let mut path = self.path(&["bucket", &bucket.to_string(), "presign"]);
+        push_key(&mut path, key)?;
+
+        self.presign(path, self.key_path(bucket, key)?, expires_in)
+            .await
+    }
+
+    /// Requests an object's data on behalf of another client, asking for
+    /// only `range` if given.
+    ///
+    /// Errors are returned as [`Error`](crate::Error) rather than
+    /// `reqwest::Error`, as the request may be sent over a Unix socket.
+    pub async fn proxy(
+        &self,
+        bucket: Uuid,
+        object: Uuid,
+        method: ProxyMethod,
+        range: Option<Range>,
+    ) -> Result<ProxyResponse<impl Stream<Item = std::io::Result<Bytes>>>> {
         let mut headers = HeaderMap::new();
 
         if let Some(range) = range {
             headers.typed_insert(range);
         }
 
-        let response = self
-            .client
-            .request(method, url)
-            .headers(headers)
-            .send()
-            .await?;
+        self.proxy_with(bucket, object, method, &headers).await
+    }
+
+    /// Requests an object's data on behalf of another client.
+    ///
+    /// The `Range` and conditional request headers in `headers` are
+    /// forwarded to the server, so that the response may be partial or
+    /// `304 Not Modified`. Other headers are ignored.
+    pub async fn proxy_with(
+        &self,
+        bucket: Uuid,
+        object: Uuid,
+        method: ProxyMethod,
+        headers: &HeaderMap,
+    ) -> Result<ProxyResponse<impl Stream<Item = std::io::Result<Bytes>>>> {
+        let method = match method {
+            ProxyMethod::Get => Method::GET,
+            ProxyMethod::Head => Method::HEAD,
+        };
+
+        let url = self.path(&[
+            "object",
+            &bucket.to_string(),
+            &object.to_string(),
+            "data",
+        ]);
+
+        let mut request = self.client.request(method, url);
+
+        for name in PROXY_REQUEST_HEADERS {
+            for value in headers.get_all(name) {
+                request = request.header(name, value.clone());
+            }
+        }
+
+        let response = request.send().await?;
 
         let status = response.status();
         let mut headers = HeaderMap::new();
 
-        for name in [
-            "accept-ranges",
-            "content-length",
-            "content-range",
-            "content-type",
-        ] {
+        for name in PROXY_RESPONSE_HEADERS {
             if let Some(value) = response.headers().get(name) {
                 headers.insert(name, value.clone());
             }
@@ -441,6 +1314,76 @@ impl Client {
             .await?)
     }
 
+    /// Uploads an object and stores it in a bucket under `key`.
+    ///
+    /// Any other object stored under the same key is removed from the
+    /// bucket.
+    pub async fn put_key<T>(
+        &self,
+        bucket: Uuid,
+        key: &str,
+        object: T,
+    ) -> Result<Object>
+    where
+        T: AsyncRead + Send + Sync + 'static,
+    {
+        self.put_key_with(bucket, key, object, &Default::default())
+            .await
+    }
+
+    pub async fn put_key_with<T>(
+        &self,
+        bucket: Uuid,
+        key: &str,
+        object: T,
+        options: &UploadOptions,
+    ) -> Result<Object>
+    where
+        T: AsyncRead + Send + Sync + 'static,
+    {
+        let stream = ReaderStream::new(object);
+        self.put_key_stream_with(bucket, key, stream, options).await
+    }
+
+    pub async fn put_key_bytes(
+        &self,
+        bucket: Uuid,
+        key: &str,
+        object: Bytes,
+    ) -> Result<Object> {
+        Ok(self
+            .client
+            .put(self.key_path(bucket, key)?)
+            .body(object)
+            .send_and_check()
+            .await?
+            .json()
+            .await?)
+    }
+
+    pub async fn put_key_stream_with<S>(
+        &self,
+        bucket: Uuid,
+        key: &str,
+        stream: S,
+        options: &UploadOptions,
+    ) -> Result<Object>
+    where
+        S: TryStream + Send + Sync + 'static,
+        S::Error: Into<Box<dyn error::Error + Send + Sync>>,
+        Bytes: From<S::Ok>,
+    {
+        Ok(self
+            .client
+            .put(self.key_path(bucket, key)?)
+            .upload_options(options)?
+            .body(Body::wrap_stream(stream))
+            .send_and_check()
+            .await?
+            .json()
+            .await?)
+    }
+
     pub async fn remove_bucket(&self, id: &Uuid) -> Result<()> {
         self.client
             .delete(self.path(&["bucket", &id.to_string()]))
@@ -493,6 +1436,27 @@ impl Client {
             .await?)
     }
 
+    /// Removes the object stored under `key` from a bucket.
+    pub async fn remove_key(&self, bucket: Uuid, key: &str) -> Result<Object> {
+        Ok(self
+            .client
+            .delete(self.key_path(bucket, key)?)
+            .send_and_check()
+            .await?
+            .json()
+            .await?)
+    }
+
+    /// Deletes an upload that has not been committed.
+    pub async fn remove_part(&self, id: Uuid) -> Result<()> {
+        self.client
+            .delete(self.path(&["part", &id.to_string()]))
+            .send_and_check()
+            .await?;
+
+        Ok(())
+    }
+
     pub async fn rename_bucket(&self, old: &Uuid, new: &str) -> Result<()> {
         let mut url = self.url.clone();
         url.path_segments_mut().unwrap().extend(&[
@@ -506,6 +1470,64 @@ impl Client {
         Ok(())
     }
 
+    /// Continues a chunked upload started by [`Client::new_upload`].
+    pub async fn resume_upload(&self, id: Uuid) -> Result<Upload> {
+        let part = self.get_part(id).await?;
+        Ok(Upload::new(self.clone(), part.id, part.size))
+    }
+
+    /// Streams every object in a bucket in the order they were added.
+    ///
+    /// Unlike paging through [`Client::get_object_pages`], all objects are
+    /// sent in a single response as newline-delimited JSON, which is parsed
+    /// as it arrives.
+    pub async fn stream_bucket_objects(
+        &self,
+        bucket: Uuid,
+    ) -> Result<impl Stream<Item = Result<Object>>> {
+        let response = self
+            .client
+            .get(self.path(&[
+                "bucket",
+                &bucket.to_string(),
+                "objects",
+                "stream",
+            ]))
+            .send_and_check()
+            .await?;
+
+        let stream = Box::pin(response.bytes_stream());
+
+        Ok(stream::try_unfold(
+            (stream, Vec::new()),
+            |(mut stream, mut buffer)| async move {
+                loop {
+                    if let Some(end) = buffer.iter().position(|b| *b == b'\n') {
+                        let line: Vec<u8> = buffer.drain(..=end).collect();
+                        let object = serde_json::from_slice(&line[..end])
+                            .map_err(|err| {
+                                Error::other(format!(
+                                    "server returned invalid object: {err}"
+                                ))
+                            })?;
+
+                        return Ok(Some((object, (stream, buffer))));
+                    }
+
+                    match stream.next().await {
+                        Some(chunk) => buffer.extend_from_slice(&chunk?),
+                        None if buffer.is_empty() => return Ok(None),
+                        None => {
+                            return Err(Error::other(
+                                "object stream ended unexpectedly".into(),
+                            ))
+                        }
+                    }
+                }
+            },
+        ))
+    }
+
     pub async fn status(&self) -> Result<StoreTotals> {
         Ok(self
             .client
@@ -515,6 +1537,67 @@ impl Client {
             .json()
             .await?)
     }
+
+    /// Changes a bucket's description and labels, returning the updated
+    /// bucket.
+    pub async fn update_bucket(
+        &self,
+        id: &Uuid,
+        patch: &BucketPatch,
+    ) -> Result<model::Bucket> {
+        Ok(self
+            .client
+            .patch(self.path(&["bucket", &id.to_string()]))
+            .json(patch)
+            .send_and_check()
+            .await?
+            .json()
+            .await?)
+    }
+
+    /// Sets entries with a value and removes entries without one from an
+    /// object's metadata, returning the updated metadata.
+    pub async fn update_user_metadata(
+        &self,
+        bucket: Uuid,
+        object: Uuid,
+        changes: &MetadataPatch,
+    ) -> Result<Metadata> {
+        Ok(self
+            .client
+            .patch(self.path(&[
+                "object",
+                &bucket.to_string(),
+                &object.to_string(),
+                "metadata",
+            ]))
+            .json(changes)
+            .send_and_check()
+            .await?
+            .json()
+            .await?)
+    }
+
+    /// Writes a chunk of data to a part starting at `offset`.
+    ///
+    /// Unlike appending, chunks written at explicit offsets may be sent in
+    /// any order and by several requests at once. The part is created if it
+    /// does not exist. Returns the range of bytes the server wrote.
+    pub async fn write_part(
+        &self,
+        part: Uuid,
+        offset: u64,
+        chunk: Bytes,
+    ) -> Result<PartRange> {
+        Ok(self
+            .client
+            .put(self.path(&["part", &part.to_string(), &offset.to_string()]))
+            .body(chunk)
+            .send_and_check()
+            .await?
+            .json()
+            .await?)
+    }
 }
 
 #[derive(Clone, Debug)]
@@ -539,10 +1622,31 @@ impl Bucket {
         self.client.add_object(self.id, object).await
     }
 
+    pub async fn add_object_with<T>(
+        &self,
+        object: T,
+        options: &UploadOptions,
+    ) -> Result<Object>
+    where
+        T: AsyncRead + Send + Sync + 'static,
+    {
+        self.client.add_object_with(self.id, object, options).await
+    }
+
     pub async fn add_object_bytes(&self, object: Bytes) -> Result<Object> {
         self.client.add_object_bytes(self.id, object).await
     }
 
+    pub async fn add_object_bytes_with(
+        &self,
+        object: Bytes,
+        options: &UploadOptions,
+    ) -> Result<Object> {
+        self.client
+            .add_object_bytes_with(self.id, object, options)
+            .await
+    }
+
     pub async fn add_object_stream<S>(&self, stream: S) -> Result<Object>
     where
         S: TryStream + Send + Sync + 'static,
@@ -552,6 +1656,21 @@ impl Bucket {
         self.client.add_object_stream(self.id, stream).await
     }
 
+    pub async fn add_object_stream_with<S>(
+        &self,
+        stream: S,
+        options: &UploadOptions,
+    ) -> Result<Object>
+    where
+        S: TryStream + Send + Sync + 'static,
+        S::Error: Into<Box<dyn error::Error + Send + Sync>>,
+        Bytes: From<S::Ok>,
+    {
+        self.client
+            .add_object_stream_with(self.id, stream, options)
+            .await
+    }
+
     pub async fn clone_as(&self, name: &str) -> Result<Self> {
         let clone = self.client.clone_bucket(self.id, name).await?;
 
@@ -561,14 +1680,55 @@ impl Bucket {
         })
     }
 
+    pub async fn export(
+        &self,
+        format: ArchiveFormat,
+        objects: Option<&[Uuid]>,
+    ) -> Result<impl Stream<Item = std::io::Result<Bytes>>> {
+        self.client.export(self.id, format, objects).await
+    }
+
     pub async fn get_all_objects(&self) -> Result<Vec<Object>> {
         self.client.get_all_objects(self.id).await
     }
 
+    pub async fn get_key_bytes(&self, key: &str) -> Result<Bytes> {
+        self.client.get_key_bytes(self.id, key).await
+    }
+
+    pub async fn get_key_stream(
+        &self,
+        key: &str,
+    ) -> Result<impl Stream<Item = std::io::Result<Bytes>>> {
+        self.client.get_key_stream(self.id, key).await
+    }
+
+    pub async fn get_keys(
+        &self,
+        prefix: &str,
+        delimiter: Option<&str>,
+    ) -> Result<KeyList> {
+        self.client.get_keys(self.id, prefix, delimiter).await
+    }
+
     pub async fn get_object(&self, id: Uuid) -> Result<Object> {
         self.client.get_object(self.id, id).await
     }
 
+    pub async fn get_object_page(
+        &self,
+        query: &ObjectQuery,
+    ) -> Result<ObjectPage> {
+        self.client.get_object_page(self.id, query).await
+    }
+
+    pub fn get_object_pages(
+        &self,
+        query: ObjectQuery,
+    ) -> impl Stream<Item = Result<ObjectPage>> {
+        self.client.get_object_pages(self.id, query)
+    }
+
     pub async fn get_objects(&self, objects: &[Uuid]) -> Result<Vec<Object>> {
         self.client.get_objects(self.id, objects).await
     }
@@ -585,6 +1745,16 @@ impl Bucket {
         self.client.get_object_bytes_range(self.id, id, range).await
     }
 
+    pub async fn get_object_bytes_ranges(
+        &self,
+        id: Uuid,
+        ranges: &[std::ops::Range<u64>],
+    ) -> Result<Vec<Bytes>> {
+        self.client
+            .get_object_bytes_ranges(self.id, id, ranges)
+            .await
+    }
+
     pub async fn get_object_stream(
         &self,
         id: Uuid,
@@ -602,17 +1772,107 @@ impl Bucket {
             .await
     }
 
+    pub async fn get_user_metadata(&self, id: Uuid) -> Result<Metadata> {
+        self.client.get_user_metadata(self.id, id).await
+    }
+
+    pub async fn head_object(&self, id: Uuid) -> Result<Object> {
+        self.client.head_object(self.id, id).await
+    }
+
+    pub async fn link_object(&self, id: Uuid) -> Result<Object> {
+        self.client.link_object(self.id, id).await
+    }
+
+    pub async fn link_object_by_hash(&self, hash: &str) -> Result<Object> {
+        self.client.link_object_by_hash(self.id, hash).await
+    }
+
+    pub async fn presign_get(
+        &self,
+        object: Uuid,
+        expires_in: Option<Duration>,
+    ) -> Result<Url> {
+        self.client.presign_get(self.id, object, expires_in).await
+    }
+
+    pub async fn presign_put(
+        &self,
+        key: &str,
+        expires_in: Option<Duration>,
+    ) -> Result<Url> {
+        self.client.presign_put(self.id, key, expires_in).await
+    }
+
     pub async fn proxy(
         &self,
         object: Uuid,
         method: ProxyMethod,
         range: Option<Range>,
-    ) -> reqwest::Result<
-        ProxyResponse<impl Stream<Item = std::io::Result<Bytes>>>,
-    > {
+    ) -> Result<ProxyResponse<impl Stream<Item = std::io::Result<Bytes>>>> {
         self.client.proxy(self.id, object, method, range).await
     }
 
+    pub async fn proxy_with(
+        &self,
+        object: Uuid,
+        method: ProxyMethod,
+        headers: &HeaderMap,
+    ) -> Result<ProxyResponse<impl Stream<Item = std::io::Result<Bytes>>>> {
+        self.client
+            .proxy_with(self.id, object, method, headers)
+            .await
+    }
+
+    pub async fn put_key<T>(&self, key: &str, object: T) -> Result<Object>
+    where
+        T: AsyncRead + Send + Sync + 'static,
+    {
+        self.client.put_key(self.id, key, object).await
+    }
+
+    pub async fn put_key_with<T>(
+        &self,
+        key: &str,
+        object: T,
+        options: &UploadOptions,
+    ) -> Result<Object>
+    where
+        T: AsyncRead + Send + Sync + 'static,
+    {
+        self.client
+            .put_key_with(self.id, key, object, options)
+            .await
+    }
+
+    pub async fn put_key_bytes(
+        &self,
+        key: &str,
+        object: Bytes,
+    ) -> Result<Object> {
+        self.client.put_key_bytes(self.id, key, object).await
+    }
+
+    pub async fn put_key_stream_with<S>(
+        &self,
+        key: &str,
+        stream: S,
+        options: &UploadOptions,
+    ) -> Result<Object>
+    where
+        S: TryStream + Send + Sync + 'static,
+        S::Error: Into<Box<dyn error::Error + Send + Sync>>,
+        Bytes: From<S::Ok>,
+    {
+        self.client
+            .put_key_stream_with(self.id, key, stream, options)
+            .await
+    }
+
+    pub async fn remove_key(&self, key: &str) -> Result<Object> {
+        self.client.remove_key(self.id, key).await
+    }
+
     pub async fn remove_object(&self, id: Uuid) -> Result<Object> {
         self.client.remove_object(self.id, id).await
     }
@@ -627,4 +1887,22 @@ impl Bucket {
     pub async fn rename(&self, name: &str) -> Result<()> {
         self.client.rename_bucket(&self.id, name).await
     }
+
+    pub async fn stream_objects(
+        &self,
+    ) -> Result<impl Stream<Item = Result<Object>>> {
+        self.client.stream_bucket_objects(self.id).await
+    }
+
+    pub async fn update(&self, patch: &BucketPatch) -> Result<model::Bucket> {
+        self.client.update_bucket(&self.id, patch).await
+    }
+
+    pub async fn update_user_metadata(
+        &self,
+        id: Uuid,
+        changes: &MetadataPatch,
+    ) -> Result<Metadata> {
+        self.client.update_user_metadata(self.id, id, changes).await
+    }
 }
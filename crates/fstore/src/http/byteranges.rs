@@ -0,0 +1,120 @@
+use crate::error::{Error, Result};
+
+use bytes::Bytes;
+use std::ops::Range;
+
+const CRLF: &[u8] = b"\r\n";
+
+fn invalid(message: &str) -> Error {
+    Error::other(format!("invalid multipart/byteranges response: {message}"))
+}
+
+fn find(haystack: &[u8], needle: &[u8]) -> Option<usize> {
+    haystack
+        .windows(needle.len())
+        .position(|window| window == needle)
+}
+
+/// A contiguous range of an object's data received from the server.
+#[derive(Debug)]
+pub struct Part {
+    pub start: u64,
+    pub data: Bytes,
+}
+
+impl Part {
+    /// Returns the bytes of `range` in this part, truncated if the part
+    /// ends first, or `None` if the part does not contain its start.
+    fn slice(&self, range: &Range<u64>) -> Option<Bytes> {
+        let end = self.start + self.data.len() as u64;
+
+        if range.start < self.start || range.start >= end {
+            return None;
+        }
+
+        let from = (range.start - self.start) as usize;
+        let to = (range.end.min(end) - self.start) as usize;
+
+        Some(self.data.slice(from..to))
+    }
+}
+
+/// Returns the bytes of `range` from whichever part contains it.
+pub fn extract(parts: &[Part], range: &Range<u64>) -> Result<Bytes> {
+    parts
+        .iter()
+        .find_map(|part| part.slice(range))
+        .ok_or_else(|| {
+            Error::other(format!(
+                "server did not return bytes {}-{}",
+                range.start,
+                range.end - 1
+            ))
+        })
+}
+
+/// Parses a `Content-Range` value of the form `bytes <first>-<last>/<size>`,
+/// returning the first and last byte positions.
+pub fn parse_content_range(value: &str) -> Option<(u64, u64)> {
+    let (range, _) = value.trim().strip_prefix("bytes ")?.split_once('/')?;
+    let (first, last) = range.split_once('-')?;
+    let (first, last) = (first.parse().ok()?, last.parse().ok()?);
+
+    (first <= last).then_some((first, last))
+}
+
+/// Splits a `multipart/byteranges` body into its parts.
+///
+/// Each part's length is taken from its `Content-Range` header, so parts
+/// may contain the boundary without ending early.
+pub fn parse(body: &Bytes, boundary: &str) -> Result<Vec<Part>> {
+    let delimiter = format!("--{boundary}");
+    let delimiter = delimiter.as_bytes();
+
+    let mut pos = find(body, delimiter)
+        .ok_or_else(|| invalid("missing boundary"))?
+        + delimiter.len();
+    let mut parts = Vec::new();
+
+    loop {
+        let rest = &body[pos..];
+
+        if rest.starts_with(b"--") {
+            return Ok(parts);
+        }
+
+        let rest = rest
+            .strip_prefix(CRLF)
+            .ok_or_else(|| invalid("expected a line break after boundary"))?;
+        let headers_len = find(rest, b"\r\n\r\n")
+            .ok_or_else(|| invalid("unterminated part headers"))?;
+        let headers = std::str::from_utf8(&rest[..headers_len])
+            .map_err(|_| invalid("part headers are not valid UTF-8"))?;
+
+        let (first, last) = headers
+            .split("\r\n")
+            .filter_map(|line| line.split_once(':'))
+            .find(|(name, _)| name.trim().eq_ignore_ascii_case("content-range"))
+            .and_then(|(_, value)| parse_content_range(value))
+            .ok_or_else(|| invalid("part without a valid Content-Range"))?;
+
+        let start = pos + CRLF.len() + headers_len + 4;
+        let end = usize::try_from(last - first + 1)
+            .ok()
+            .and_then(|len| start.checked_add(len))
+            .filter(|&end| end <= body.len())
+            .ok_or_else(|| invalid("part is shorter than its Content-Range"))?;
+
+        parts.push(Part {
+            start: first,
+            data: body.slice(start..end),
+        });
+
+        body[end..]
+            .strip_prefix(CRLF)
+            .and_then(|rest| rest.strip_prefix(delimiter))
+            .ok_or_else(|| invalid("missing boundary after part"))?;
+
+        pos = end + CRLF.len() + delimiter.len();
+    }
+}
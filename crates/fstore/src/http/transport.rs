@@ -0,0 +1,169 @@
+#[cfg(unix)]
+use super::unix;
+
+use crate::error::{Error, Result};
+
+use reqwest::{
+    header::{HeaderMap, HeaderName, HeaderValue},
+    Body, Method, Response, Url,
+};
+use serde::Serialize;
+use std::fmt::Display;
+
+#[cfg(unix)]
+use http_body_util::BodyExt;
+#[cfg(unix)]
+use hyper_util::{client::legacy, rt::TokioExecutor};
+#[cfg(unix)]
+use std::{error, path::PathBuf};
+
+#[cfg(unix)]
+type UnixClient = legacy::Client<unix::Connector, Body>;
+
+fn request_failed(err: impl Display) -> Error {
+    Error::other(format!("Request failed: {err}"))
+}
+
+#[cfg(unix)]
+async fn send_unix(
+    socket: &UnixClient,
+    builder: reqwest::RequestBuilder,
+) -> Result<Response> {
+    let request = builder.build().map_err(request_failed)?;
+    let request = http::Request::try_from(request).map_err(request_failed)?;
+    let response =
+        socket.request(request).await.map_err(
+            |err| match error::Error::source(&err) {
+                Some(source) => request_failed(format!("{err}: {source}")),
+                None => request_failed(err),
+            },
+        )?;
+
+    Ok(Response::from(
+        response.map(|body| Body::wrap_stream(body.into_data_stream())),
+    ))
+}
+
+/// Sends requests to the server over TCP, or over a Unix socket if one is
+/// configured.
+#[derive(Clone, Debug)]
+pub struct Transport {
+    client: reqwest::Client,
+    headers: HeaderMap,
+    #[cfg(unix)]
+    socket: Option<UnixClient>,
+}
+
+impl Transport {
+    pub fn new(client: reqwest::Client, headers: HeaderMap) -> Self {
+        Self {
+            client,
+            headers,
+            #[cfg(unix)]
+            socket: None,
+        }
+    }
+
+    /// Sends requests over the Unix socket at `path`.
+    #[cfg(unix)]
+    pub fn unix_socket(self, path: PathBuf) -> Self {
+        Self {
+            socket: Some(
+                legacy::Client::builder(TokioExecutor::new())
+                    .build(unix::Connector::new(path)),
+            ),
+            ..self
+        }
+    }
+
+    pub fn delete(&self, url: Url) -> RequestBuilder {
+        self.request(Method::DELETE, url)
+    }
+
+    pub fn get(&self, url: Url) -> RequestBuilder {
+        self.request(Method::GET, url)
+    }
+
+    pub fn head(&self, url: Url) -> RequestBuilder {
+        self.request(Method::HEAD, url)
+    }
+
+    pub fn patch(&self, url: Url) -> RequestBuilder {
+        self.request(Method::PATCH, url)
+    }
+
+    pub fn post(&self, url: Url) -> RequestBuilder {
+        self.request(Method::POST, url)
+    }
+
+    pub fn put(&self, url: Url) -> RequestBuilder {
+        self.request(Method::PUT, url)
+    }
+
+    pub fn request(&self, method: Method, url: Url) -> RequestBuilder {
+        RequestBuilder {
+            builder: self
+                .client
+                .request(method, url)
+                .headers(self.headers.clone()),
+            #[cfg(unix)]
+            socket: self.socket.clone(),
+        }
+    }
+}
+
+/// A request that is sent with the [`Transport`] that created it.
+#[derive(Debug)]
+pub struct RequestBuilder {
+    builder: reqwest::RequestBuilder,
+    #[cfg(unix)]
+    socket: Option<UnixClient>,
+}
+
+impl RequestBuilder {
+    fn map<F>(self, f: F) -> Self
+    where
+        F: FnOnce(reqwest::RequestBuilder) -> reqwest::RequestBuilder,
+    {
+        Self {
+            builder: f(self.builder),
+            #[cfg(unix)]
+            socket: self.socket,
+        }
+    }
+
+    pub fn body<T: Into<Body>>(self, body: T) -> Self {
+        self.map(|builder| builder.body(body))
+    }
+
+    pub fn header<K, V>(self, key: K, value: V) -> Self
+    where
+        HeaderName: TryFrom<K>,
+        <HeaderName as TryFrom<K>>::Error: Into<http::Error>,
+        HeaderValue: TryFrom<V>,
+        <HeaderValue as TryFrom<V>>::Error: Into<http::Error>,
+    {
+        self.map(|builder| builder.header(key, value))
+    }
+
+    pub fn headers(self, headers: HeaderMap) -> Self {
+        self.map(|builder| builder.headers(headers))
+    }
+
+    pub fn json<T: Serialize + ?Sized>(self, json: &T) -> Self {
+        self.map(|builder| builder.json(json))
+    }
+
+    pub fn query<T: Serialize + ?Sized>(self, query: &T) -> Self {
+        self.map(|builder| builder.query(query))
+    }
+
+    pub async fn send(self) -> Result<Response> {
+        #[cfg(unix)]
+        if let Some(socket) = self.socket {
+            return send_unix(&socket, self.builder).await;
+        }
+
+        self.builder.send().await.map_err(request_failed)
+    }
+}
@@ -0,0 +1,129 @@
+use super::{Client, UploadOptions};
+
+use crate::{
+    error::{Error, Result},
+    Object, PartRange,
+};
+
+use bytes::Bytes;
+use std::io::SeekFrom;
+use tokio::io::{AsyncRead, AsyncReadExt, AsyncSeek, AsyncSeekExt};
+use uuid::Uuid;
+
+/// The default number of bytes sent per request by [`Upload::upload_from`].
+pub const DEFAULT_CHUNK_SIZE: usize = 8 * 1024 * 1024;
+
+/// An object upload that is sent to the server in chunks and can be resumed
+/// if interrupted.
+///
+/// Data is stored in a part on the server until the upload is committed to
+/// a bucket.
+#[derive(Clone, Debug)]
+pub struct Upload {
+    client: Client,
+    id: Uuid,
+    size: u64,
+}
+
+impl Upload {
+    pub(super) fn new(client: Client, id: Uuid, size: u64) -> Self {
+        Self { client, id, size }
+    }
+
+    /// Returns the ID of the part holding the uploaded data.
+    pub fn id(&self) -> Uuid {
+        self.id
+    }
+
+    /// Returns the number of bytes the server is known to have received.
+    pub fn size(&self) -> u64 {
+        self.size
+    }
+
+    /// Queries the server for the number of bytes it has received.
+    ///
+    /// This should be called before continuing an upload after a failed
+    /// request, as the server may have stored some of the failed request's
+    /// data.
+    pub async fn refresh(&mut self) -> Result<u64> {
+        self.size = self.client.get_part(self.id).await?.size;
+        Ok(self.size)
+    }
+
+    /// Discards the data uploaded so far.
+    pub async fn abort(self) -> Result<()> {
+        self.client.remove_part(self.id).await
+    }
+
+    /// Sends a chunk of data to be appended to the upload.
+    pub async fn append(&mut self, chunk: Bytes) -> Result<()> {
+        self.size += self.client.append_part(self.id, chunk).await?;
+        Ok(())
+    }
+
+    /// Uploads the contents of `reader` in chunks of `chunk_size` bytes,
+    /// skipping the data the server has already received.
+    pub async fn upload_from<R>(
+        &mut self,
+        reader: &mut R,
+        chunk_size: usize,
+    ) -> Result<()>
+    where
+        R: AsyncRead + AsyncSeek + Unpin,
+    {
+        reader
+            .seek(SeekFrom::Start(self.size))
+            .await
+            .map_err(|err| {
+                Error::other(format!(
+                    "failed to seek to offset {} of upload data: {err}",
+                    self.size
+                ))
+            })?;
+
+        loop {
+            let mut chunk = Vec::with_capacity(chunk_size);
+
+            (&mut *reader)
+                .take(chunk_size as u64)
+                .read_to_end(&mut chunk)
+                .await
+                .map_err(|err| {
+                    Error::other(format!("failed to read upload data: {err}"))
+                })?;
+
+            if chunk.is_empty() {
+                return Ok(());
+            }
+
+            self.append(chunk.into()).await?;
+        }
+    }
+
+    /// Returns the byte ranges the server has received.
+    pub async fn ranges(&self) -> Result<Vec<PartRange>> {
+        self.client.get_part_ranges(self.id).await
+    }
+
+    /// Writes a chunk of data at `offset`.
+    ///
+    /// Chunks may be written in any order and concurrently. Once chunks
+    /// have been written this way, the upload can no longer be appended to,
+    /// and committing it requires [`UploadOptions::size`].
+    pub async fn write_at(
+        &self,
+        offset: u64,
+        chunk: Bytes,
+    ) -> Result<PartRange> {
+        self.client.write_part(self.id, offset, chunk).await
+    }
+
+    /// Adds the uploaded data to a bucket as a new object.
+    pub async fn commit(
+        self,
+        bucket: Uuid,
+        options: &UploadOptions,
+    ) -> Result<Object> {
+        self.client.commit_part(bucket, self.id, options).await
+    }
+}
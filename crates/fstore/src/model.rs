@@ -2,9 +2,24 @@ pub use uuid::Uuid;
 
 use chrono::Local;
 use serde::{Deserialize, Serialize};
+use std::{
+    collections::BTreeMap,
+    fmt::{self, Display},
+    str::FromStr,
+};
 
 pub type DateTime = chrono::DateTime<Local>;
 
+/// User-defined labels of a bucket, such as its owner.
+pub type Labels = BTreeMap<String, String>;
+
+/// User-defined properties of an object within a bucket.
+pub type Metadata = BTreeMap<String, String>;
+
+/// Changes to an object's metadata: entries with a value are set, and
+/// entries without one are removed.
+pub type MetadataPatch = BTreeMap<String, Option<String>>;
+
 #[derive(Clone, Debug, Deserialize, Serialize)]
 pub struct About {
     pub version: Version,
@@ -30,6 +45,88 @@ pub struct Bucket {
     pub created: DateTime,
     pub object_count: u64,
     pub space_used: u64,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+    #[serde(default, skip_serializing_if = "Labels::is_empty")]
+    pub labels: Labels,
+}
+
+/// Changes to a bucket's description and labels.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct BucketPatch {
+    /// The bucket's new description. An empty string removes it.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+
+    /// Labels with a value are set, and labels without one are removed.
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub labels: BTreeMap<String, Option<String>>,
+}
+
+/// Selects buckets by their labels.
+///
+/// A bucket matches if it has every label in `labels` with the given value
+/// and a label for every name in `names` with any value. The string form is
+/// a comma-separated list of `name=value` and `name` entries.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(try_from = "String", into = "String")]
+pub struct LabelSelector {
+    pub labels: Labels,
+    pub names: Vec<String>,
+}
+
+impl LabelSelector {
+    pub fn is_empty(&self) -> bool {
+        self.labels.is_empty() && self.names.is_empty()
+    }
+}
+
+impl Display for LabelSelector {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let labels = self
+            .labels
+            .iter()
+            .map(|(name, value)| format!("{name}={value}"));
+        let names = self.names.iter().cloned();
+
+        f.write_str(&labels.chain(names).collect::<Vec<_>>().join(","))
+    }
+}
+
+impl FromStr for LabelSelector {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut selector = Self::default();
+
+        for entry in s.split(',').filter(|entry| !entry.is_empty()) {
+            match entry.split_once('=') {
+                Some(("", _)) => {
+                    return Err(format!("missing label name in '{entry}'"))
+                }
+                Some((name, value)) => {
+                    selector.labels.insert(name.into(), value.into());
+                }
+                None => selector.names.push(entry.into()),
+            }
+        }
+
+        Ok(selector)
+    }
+}
+
+impl From<LabelSelector> for String {
+    fn from(value: LabelSelector) -> Self {
+        value.to_string()
+    }
+}
+
+impl TryFrom<String> for LabelSelector {
+    type Error = String;
+
+    fn try_from(value: String) -> Result<Self, Self::Error> {
+        value.parse()
+    }
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -41,12 +138,138 @@ pub struct Object {
     pub subtype: String,
     pub extension: Option<String>,
     pub added: DateTime,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub key: Option<String>,
+    #[serde(default, skip_serializing_if = "Metadata::is_empty")]
+    pub metadata: Metadata,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub filename: Option<String>,
+}
+
+/// The result of listing the keys in a bucket.
+///
+/// When a delimiter is given, keys containing the delimiter after the prefix
+/// are not listed individually, but are instead rolled up into `prefixes`,
+/// each ending with the delimiter.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct KeyList {
+    pub objects: Vec<Object>,
+    pub prefixes: Vec<String>,
 }
 
 impl Object {
     pub fn media_type(&self) -> String {
         format!("{}/{}", self.r#type, self.subtype)
     }
+
+    /// Returns a filename for the object's data made of its ID and the
+    /// extension for its media type, if there is one.
+    pub fn default_filename(&self) -> String {
+        match &self.extension {
+            Some(extension) => format!("{}.{extension}", self.id),
+            None => self.id.to_string(),
+        }
+    }
+}
+
+/// The formats in which objects can be exported.
+#[derive(
+    Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize,
+)]
+#[serde(rename_all = "lowercase")]
+pub enum ArchiveFormat {
+    #[default]
+    Tar,
+    Zip,
+}
+
+impl ArchiveFormat {
+    pub fn extension(self) -> &'static str {
+        match self {
+            Self::Tar => "tar",
+            Self::Zip => "zip",
+        }
+    }
+
+    pub fn media_type(self) -> &'static str {
+        match self {
+            Self::Tar => "application/x-tar",
+            Self::Zip => "application/zip",
+        }
+    }
+}
+
+impl Display for ArchiveFormat {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.extension())
+    }
+}
+
+impl FromStr for ArchiveFormat {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "tar" => Ok(Self::Tar),
+            "zip" => Ok(Self::Zip),
+            _ => Err(format!("expected 'tar' or 'zip', found '{s}'")),
+        }
+    }
+}
+
+/// Grants temporary access to a single request without an API token.
+///
+/// The fields are sent as the `expires` and `signature` query parameters of
+/// the presigned URL.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Signature {
+    /// Unix time in seconds after which the signature is rejected.
+    pub expires: u64,
+
+    pub signature: String,
+}
+
+/// One page of the objects in a bucket.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ObjectPage {
+    pub objects: Vec<Object>,
+
+    /// A cursor for fetching the following page, or `None` if this is the
+    /// last page.
+    pub next: Option<String>,
+}
+
+/// Selects a page of the objects in a bucket.
+///
+/// Objects are ordered by the time they were added to the bucket. All
+/// filters are optional and are combined.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ObjectQuery {
+    /// Only return objects following this cursor from a previous page.
+    pub after: Option<String>,
+
+    /// The maximum number of objects to return.
+    ///
+    /// The server uses its own default if omitted and caps large values.
+    pub limit: Option<u32>,
+
+    /// Only return objects with this media type, such as `image`.
+    pub r#type: Option<String>,
+
+    /// Only return objects with this media subtype, such as `png`.
+    pub subtype: Option<String>,
+
+    /// Only return objects of at least this many bytes.
+    pub min_size: Option<u64>,
+
+    /// Only return objects of at most this many bytes.
+    pub max_size: Option<u64>,
+
+    /// Only return objects added to the bucket at or after this time.
+    pub added_after: Option<DateTime>,
+
+    /// Only return objects added to the bucket before this time.
+    pub added_before: Option<DateTime>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -55,6 +278,22 @@ pub struct ObjectError {
     pub message: String,
 }
 
+/// An upload in progress that has not yet been committed as an object.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Part {
+    pub id: Uuid,
+    pub size: u64,
+    pub modified: DateTime,
+}
+
+/// A span of bytes written to a part, from `start` up to but not including
+/// `end`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PartRange {
+    pub start: u64,
+    pub end: u64,
+}
+
 #[derive(Debug, Default, Clone, Serialize, Deserialize)]
 pub struct RemoveResult {
     pub objects_removed: u64,
@@ -5,6 +5,7 @@ use serde::{Deserialize, Serialize};
 use std::{
     fs,
     path::{Path, PathBuf},
+    time::Duration,
 };
 use timber::Sink;
 
@@ -21,12 +22,54 @@ pub struct Config {
     #[serde(default)]
     pub log: Log,
 
+    #[serde(default)]
+    pub parts: Parts,
+
     pub user: Option<String>,
 }
 
 #[derive(Serialize, Deserialize, Debug)]
 pub struct Http {
-    pub listen: Vec<Endpoint>,
+    pub listen: Vec<Listen>,
+
+    /// Require requests to carry an API token permitting the operation.
+    #[serde(default)]
+    pub auth: bool,
+
+    /// Secret used to sign presigned URLs. Presigned URLs are disabled if
+    /// this is not set.
+    pub signing_key: Option<String>,
+
+    /// `Cache-Control` header sent with object data, such as
+    /// `public, max-age=31536000, immutable`. No header is sent if this is
+    /// not set.
+    ///
+    /// The header is only sent when data is requested by object ID, as the
+    /// object stored under a key may change.
+    pub cache_control: Option<String>,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+#[serde(untagged)]
+pub enum Listen {
+    /// A TCP address serving HTTPS.
+    Tls { address: String, tls: Tls },
+
+    /// A TCP address or Unix domain socket serving plain HTTP.
+    Plain(Endpoint),
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct Tls {
+    /// PEM file containing the server's certificate chain.
+    pub certificate: PathBuf,
+
+    /// PEM file containing the certificate's private key.
+    pub key: PathBuf,
+
+    /// PEM file containing the CA certificates that client certificates
+    /// are verified against. If set, clients must present a certificate.
+    pub client_ca: Option<PathBuf>,
 }
 
 #[derive(Serialize, Deserialize, Debug)]
@@ -53,6 +96,44 @@ impl Default for Log {
     }
 }
 
+#[derive(Serialize, Deserialize, Debug)]
+pub struct Parts {
+    /// Seconds after its last write that an abandoned upload is deleted.
+    #[serde(default = "Parts::default_ttl")]
+    pub ttl: u64,
+
+    /// Seconds between checks for expired uploads.
+    #[serde(default = "Parts::default_sweep_interval")]
+    pub sweep_interval: u64,
+}
+
+impl Parts {
+    fn default_ttl() -> u64 {
+        24 * 60 * 60
+    }
+
+    fn default_sweep_interval() -> u64 {
+        60 * 60
+    }
+
+    pub fn ttl(&self) -> Duration {
+        Duration::from_secs(self.ttl)
+    }
+
+    pub fn sweep_interval(&self) -> Duration {
+        Duration::from_secs(self.sweep_interval.max(1))
+    }
+}
+
+impl Default for Parts {
+    fn default() -> Self {
+        Self {
+            ttl: Self::default_ttl(),
+            sweep_interval: Self::default_sweep_interval(),
+        }
+    }
+}
+
 pub fn read(path: &Path) -> Result<Config, String> {
     let data = fs::read_to_string(path).map_err(|err| {
         format!("Failed to read config file '{}': {err}", path.display())
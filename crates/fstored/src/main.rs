@@ -7,11 +7,12 @@ use fstored::{
     server, store, ObjectStore, Result,
 };
 
-use clap::{Parser, Subcommand};
-use fstore_core::Version;
+use clap::{Parser, Subcommand, ValueEnum};
+use fstore_core::{OrphanAction, Scope, Version};
 use log::error;
 use shadow_rs::shadow;
 use std::{future::Future, path::PathBuf, process::ExitCode, sync::Arc};
+use uuid::Uuid;
 
 shadow!(build);
 
@@ -43,6 +44,78 @@ pub struct Cli {
     command: Command,
 }
 
+#[derive(Clone, Copy, Default, ValueEnum)]
+enum Orphans {
+    /// List orphaned entries
+    #[default]
+    Report,
+
+    /// Move orphaned entries into the quarantine directory
+    Quarantine,
+
+    /// Delete orphaned entries
+    Delete,
+}
+
+impl From<Orphans> for OrphanAction {
+    fn from(value: Orphans) -> Self {
+        match value {
+            Orphans::Report => Self::Report,
+            Orphans::Quarantine => Self::Quarantine,
+            Orphans::Delete => Self::Delete,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Default, ValueEnum)]
+enum TokenScope {
+    /// Retrieve buckets and objects
+    #[default]
+    Read,
+
+    /// Add, change and remove buckets and objects
+    Write,
+
+    /// Manage the store as a whole
+    Admin,
+}
+
+impl From<TokenScope> for Scope {
+    fn from(value: TokenScope) -> Self {
+        match value {
+            TokenScope::Read => Self::Read,
+            TokenScope::Write => Self::Write,
+            TokenScope::Admin => Self::Admin,
+        }
+    }
+}
+
+#[derive(Subcommand)]
+enum Token {
+    /// Create a new API token and print its secret
+    Create {
+        /// Name describing who or what uses the token
+        name: String,
+
+        #[arg(short, long, value_enum, default_value_t)]
+        /// Operations the token permits
+        scope: TokenScope,
+
+        #[arg(short, long = "bucket", value_name = "BUCKET")]
+        /// Restrict the token to a bucket; may be repeated
+        buckets: Vec<Uuid>,
+    },
+
+    /// List API tokens
+    List,
+
+    /// Revoke an API token
+    Revoke {
+        /// Token ID
+        id: Uuid,
+    },
+}
+
 #[derive(Subcommand)]
 enum Command {
     /// Create a backup of the database and object files
@@ -62,6 +135,11 @@ enum Command {
         #[arg(short, long)]
         /// Do not show progress
         quiet: bool,
+
+        #[arg(long, value_enum, default_value_t)]
+        /// What to do with files in the objects directory that have no
+        /// database record or do not belong there
+        orphans: Orphans,
     },
 
     /// Initialize the database
@@ -73,6 +151,21 @@ enum Command {
     /// Update schemas to match the current program version
     Migrate,
 
+    /// Delete object files that have no database record
+    Reclaim,
+
+    /// Replace objects with recorded errors by their archived copies
+    Repair {
+        /// Directory containing the archive
+        ///
+        /// If omitted, the config file's 'archive' setting is used
+        directory: Option<PathBuf>,
+
+        #[arg(short, long)]
+        /// Do not show progress
+        quiet: bool,
+    },
+
     /// Restore database data and object files from a backup
     Restore {
         /// Directory to restore data from
@@ -84,6 +177,25 @@ enum Command {
         ///
         /// Restoring may require superuser privileges
         user: Option<String>,
+
+        #[arg(short = 'n', long)]
+        /// Report which object files would be restored without changing
+        /// anything
+        ///
+        /// The archive's database dump is checked for readability but not
+        /// loaded. Each archived object file is read and compared against
+        /// its copy in the home directory
+        dry_run: bool,
+
+        #[arg(short, long)]
+        /// Do not show progress
+        quiet: bool,
+    },
+
+    /// Manage API tokens
+    Token {
+        #[command(subcommand)]
+        command: Token,
     },
 
     /// Start the web server
@@ -243,9 +355,9 @@ async fn run_async(
             })
             .await
         }
-        Command::Check { quiet } => {
+        Command::Check { quiet, orphans } => {
             store(&config, |store| async move {
-                let (progress, handle) = store.check().await?;
+                let (progress, handle) = store.check((*orphans).into()).await?;
 
                 let bar = if *quiet {
                     None
@@ -269,7 +381,7 @@ async fn run_async(
                     bar.cancel().await;
                 }
 
-                result??;
+                let report = result??;
 
                 let completed = progress.completed();
                 let errors = progress.errors();
@@ -295,6 +407,33 @@ async fn run_async(
                     }
                 );
 
+                for orphan in &report.orphans {
+                    println!("{}", orphan.path.display());
+                    println!("\t{}", orphan.reason);
+                }
+
+                let total = report.orphans.len();
+
+                if total > 0 {
+                    println!(
+                        "{} orphaned entr{} {}",
+                        total,
+                        match total {
+                            1 => "y",
+                            _ => "ies",
+                        },
+                        match report.quarantine {
+                            Some(ref path) => {
+                                format!("moved to '{}'", path.display())
+                            }
+                            None => match orphans {
+                                Orphans::Delete => "deleted".into(),
+                                _ => "found".into(),
+                            },
+                        }
+                    );
+                }
+
                 Ok(())
             })
             .await
@@ -317,7 +456,90 @@ async fn run_async(
             })
             .await
         }
-        Command::Restore { directory, user } => {
+        Command::Reclaim => {
+            store(&config, |store| async move {
+                let result = store.reclaim().await?;
+                let total = result.objects_removed;
+
+                println!(
+                    "Reclaimed {} object file{} freeing {} byte{}",
+                    total,
+                    match total {
+                        1 => "",
+                        _ => "s",
+                    },
+                    result.space_freed,
+                    match result.space_freed {
+                        1 => "",
+                        _ => "s",
+                    }
+                );
+
+                Ok(())
+            })
+            .await
+        }
+        Command::Repair { directory, quiet } => {
+            if let Some(archive) = directory {
+                config.archive = Some(archive.clone());
+            }
+
+            store(&config, |store| async move {
+                let (progress, handle) = store.repair().await?;
+
+                let bar = if *quiet {
+                    None
+                } else {
+                    let total = progress.total();
+                    let title = format!(
+                        "Repairing {} object{}...",
+                        total,
+                        match total {
+                            1 => "",
+                            _ => "s",
+                        }
+                    );
+
+                    Some(ProgressBarTask::new(title, progress.clone()))
+                };
+
+                let result = handle.await;
+
+                if let Some(bar) = bar {
+                    bar.cancel().await;
+                }
+
+                let failed = result??;
+                let repaired = progress.completed() - failed.len() as u64;
+
+                for error in &failed {
+                    println!("{}", error.object_id);
+                    println!("\t{}", error.message);
+                }
+
+                println!(
+                    "Repaired {} object{}{}",
+                    repaired,
+                    match repaired {
+                        1 => "",
+                        _ => "s",
+                    },
+                    match failed.len() {
+                        0 => "".into(),
+                        len => format!(" ({} could not be repaired)", len),
+                    }
+                );
+
+                Ok(())
+            })
+            .await
+        }
+        Command::Restore {
+            directory,
+            user,
+            dry_run,
+            quiet,
+        } => {
             if let Some(user) = user {
                 config
                     .database
@@ -332,14 +554,115 @@ async fn run_async(
             }?;
 
             store(&config, |store| async move {
-                store.restore(archive).await?;
+                let (progress, handle) =
+                    store.restore(archive, *dry_run).await?;
+
+                let bar = if *quiet {
+                    None
+                } else {
+                    let total = progress.total();
+                    let title = format!(
+                        "Restoring {} object{}...",
+                        total,
+                        match total {
+                            1 => "",
+                            _ => "s",
+                        }
+                    );
+
+                    Some(ProgressBarTask::new(title, progress.clone()))
+                };
+
+                let result = handle.await;
+
+                if let Some(bar) = bar {
+                    bar.cancel().await;
+                }
+
+                let restored = result??;
+                let errors = progress.errors();
+
+                println!(
+                    "{} {} object file{} from archive{}",
+                    match dry_run {
+                        true => "Would restore",
+                        false => "Restored",
+                    },
+                    restored,
+                    match restored {
+                        1 => "",
+                        _ => "s",
+                    },
+                    match errors {
+                        0 => "".into(),
+                        _ => format!(
+                            " ({} error{})",
+                            errors,
+                            match errors {
+                                1 => "",
+                                _ => "s",
+                            }
+                        ),
+                    }
+                );
+
+                Ok(())
+            })
+            .await
+        }
+        Command::Token { command } => {
+            store(&config, |store| async move {
+                match command {
+                    Token::Create {
+                        name,
+                        scope,
+                        buckets,
+                    } => {
+                        let buckets =
+                            (!buckets.is_empty()).then_some(buckets.as_slice());
+                        let (token, secret) = store
+                            .create_token(name, (*scope).into(), buckets)
+                            .await?;
+
+                        eprintln!(
+                            "Created token {}; the secret below cannot be \
+                            shown again",
+                            token.id
+                        );
+                        println!("{secret}");
+                    }
+                    Token::List => {
+                        for token in store.get_tokens().await? {
+                            println!("{}", token.id);
+                            println!(
+                                "\t{} ({}, {}) created {}",
+                                token.name,
+                                token.scope,
+                                match &token.buckets {
+                                    Some(buckets) => buckets
+                                        .iter()
+                                        .map(Uuid::to_string)
+                                        .collect::<Vec<_>>()
+                                        .join(", "),
+                                    None => "all buckets".into(),
+                                },
+                                token.created.format("%Y-%m-%d %H:%M:%S")
+                            );
+                        }
+                    }
+                    Token::Revoke { id } => {
+                        let token = store.revoke_token(id).await?;
+                        println!("Revoked token '{}'", token.name);
+                    }
+                }
+
                 Ok(())
             })
             .await
         }
         Command::Serve { .. } => {
             store(&config, |store| async {
-                server::serve(&config.http, store, parent).await
+                server::serve(&config.http, &config.parts, store, parent).await
             })
             .await
         }
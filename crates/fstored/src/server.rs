@@ -1,12 +1,31 @@
+mod auth;
+mod byteranges;
+mod conditional;
+mod digest;
+mod disposition;
 mod error;
+mod export;
+mod extract;
+mod metadata;
+mod presign;
 mod router;
+mod tls;
 
-use crate::{conf::Http, Result};
+use crate::{
+    conf::{Http, Listen, Parts},
+    Result,
+};
 
+use axum::{http::HeaderValue, middleware, Extension};
 use axum_unix::shutdown_signal;
 use fstore_core::ObjectStore;
-use log::{error, info};
+use log::{error, info, warn};
 use std::sync::Arc;
+use tokio::{
+    signal::unix::{signal, SignalKind},
+    task::JoinHandle,
+    time,
+};
 use tokio_util::sync::CancellationToken;
 
 #[derive(Clone)]
@@ -16,6 +35,7 @@ struct AppState {
 
 pub async fn serve(
     config: &Http,
+    parts: &Parts,
     store: Arc<ObjectStore>,
     parent: &mut dmon::Parent,
 ) -> Result {
@@ -26,22 +46,74 @@ pub async fn serve(
 
     store.prepare().await?;
 
-    let app = router::routes().with_state(AppState { store });
     let token = CancellationToken::new();
+    let sweeper = sweep_parts(parts, store.clone(), token.clone());
+    let state = AppState { store };
+    let mut app = router::routes();
+
+    if config.auth {
+        app = app.route_layer(middleware::from_fn_with_state(
+            state.clone(),
+            auth::authorize,
+        ));
+    } else {
+        warn!("Authentication is disabled: all requests are permitted");
+    }
+
+    if let Some(key) = &config.signing_key {
+        let signer = presign::Signer::new(key);
+
+        app = app
+            .route_layer(middleware::from_fn_with_state(
+                signer.clone(),
+                presign::verify,
+            ))
+            .layer(Extension(signer));
+    }
+
+    if let Some(value) = &config.cache_control {
+        let value = HeaderValue::from_str(value).map_err(|err| {
+            format!("Invalid Cache-Control value '{value}': {err}")
+        })?;
+
+        app = app.layer(Extension(conditional::CacheControl(value)));
+    }
+
+    let app = app.with_state(state);
 
     let mut handles = Vec::new();
+    let mut acceptors = Vec::new();
 
-    for endpoint in &config.listen {
-        let handle =
-            axum_unix::serve(endpoint, app.clone(), token.clone(), |_| {
-                if let Err(err) = parent.notify() {
-                    error!(
-                        "Failed to notify parent process of \
-                        successful start: {err}"
-                    );
+    for listen in &config.listen {
+        let handle = match listen {
+            Listen::Plain(endpoint) => {
+                axum_unix::serve(endpoint, app.clone(), token.clone(), |_| {
+                    notify(parent)
+                })
+                .await
+            }
+            Listen::Tls { address, tls } => match tls::Acceptor::new(tls) {
+                Ok(acceptor) => {
+                    let acceptor = Arc::new(acceptor);
+                    acceptors.push(acceptor.clone());
+
+                    let handle = tls::serve(
+                        address,
+                        acceptor,
+                        app.clone(),
+                        token.clone(),
+                    )
+                    .await;
+
+                    if handle.is_ok() {
+                        notify(parent);
+                    }
+
+                    handle
                 }
-            })
-            .await;
+                Err(err) => Err(err),
+            },
+        };
 
         match handle {
             Ok(handle) => handles.push(handle),
@@ -50,9 +122,13 @@ pub async fn serve(
     }
 
     if handles.is_empty() {
+        token.cancel();
         return Err("No servers could be started".into());
     }
 
+    let reloader = (!acceptors.is_empty())
+        .then(|| reload_on_hangup(acceptors, token.clone()));
+
     shutdown_signal().await;
     token.cancel();
     info!("Server shutting down");
@@ -63,5 +139,75 @@ pub async fn serve(
         }
     }
 
+    if let Err(err) = sweeper.await {
+        error!("Failed to join part sweeper task: {err}");
+    }
+
+    if let Some(reloader) = reloader {
+        if let Err(err) = reloader.await {
+            error!("Failed to join certificate reload task: {err}");
+        }
+    }
+
     Ok(())
 }
+
+fn notify(parent: &mut dmon::Parent) {
+    if let Err(err) = parent.notify() {
+        error!("Failed to notify parent process of successful start: {err}");
+    }
+}
+
+/// Reloads the TLS certificates of every listener when the process
+/// receives `SIGHUP`.
+fn reload_on_hangup(
+    acceptors: Vec<Arc<tls::Acceptor>>,
+    token: CancellationToken,
+) -> JoinHandle<()> {
+    tokio::spawn(async move {
+        let mut hangup = match signal(SignalKind::hangup()) {
+            Ok(hangup) => hangup,
+            Err(err) => {
+                error!("Failed to listen for SIGHUP: {err}");
+                return;
+            }
+        };
+
+        loop {
+            tokio::select! {
+                _ = token.cancelled() => break,
+                _ = hangup.recv() => {
+                    info!("Reloading TLS certificates");
+
+                    for acceptor in &acceptors {
+                        if let Err(err) = acceptor.reload() {
+                            error!("Failed to reload TLS certificates: {err}");
+                        }
+                    }
+                }
+            }
+        }
+    })
+}
+
+fn sweep_parts(
+    config: &Parts,
+    store: Arc<ObjectStore>,
+    token: CancellationToken,
+) -> JoinHandle<()> {
+    let ttl = config.ttl();
+    let mut interval = time::interval(config.sweep_interval());
+
+    tokio::spawn(async move {
+        loop {
+            tokio::select! {
+                _ = token.cancelled() => break,
+                _ = interval.tick() => {
+                    if let Err(err) = store.remove_expired_parts(ttl).await {
+                        error!("Failed to remove expired parts: {err}");
+                    }
+                }
+            }
+        }
+    })
+}
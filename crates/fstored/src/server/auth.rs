@@ -0,0 +1,211 @@
+use crate::server::{
+    error::{error_response, Error},
+    presign::Presigned,
+    AppState,
+};
+
+use axum::{
+    extract::{Extension, MatchedPath, RawPathParams, Request, State},
+    http::{header::WWW_AUTHENTICATE, Method, StatusCode},
+    middleware::Next,
+    response::{IntoResponse, Response},
+};
+use axum_extra::{
+    headers::{authorization::Bearer, Authorization},
+    TypedHeader,
+};
+use fstore::ErrorCode;
+use fstore_core::{ObjectStore, Scope, Token};
+use uuid::Uuid;
+
+/// What a request's token must permit.
+#[derive(Clone, Copy, Debug)]
+enum Access {
+    /// Any token with the scope, such as for uploads that are not yet part
+    /// of a bucket.
+    Any(Scope),
+
+    /// A token with the scope that may access the bucket identified by the
+    /// route's first path parameter.
+    Bucket(Scope),
+
+    /// A token with the scope that is not restricted to specific buckets.
+    Store(Scope),
+}
+
+impl Access {
+    /// Returns the access required by a route. Routes not listed here
+    /// require an unrestricted admin token.
+    fn of(method: &Method, path: &str) -> Self {
+        use Access::*;
+        use Scope::*;
+
+        let method = match *method == Method::HEAD {
+            true => "GET",
+            false => method.as_str(),
+        };
+
+        match (method, path) {
+            ("GET", "/") => Any(Read),
+            ("GET", "/bucket/:bucket") => Bucket(Read),
+            ("PUT", "/bucket/:bucket") => Store(Write),
+            ("POST" | "PATCH", "/bucket/:bucket") => Bucket(Write),
+            ("DELETE", "/bucket/:bucket") => Bucket(Admin),
+            ("GET", "/bucket/:name/objects") => Bucket(Read),
+            ("DELETE", "/bucket/:name/objects") => Bucket(Write),
+            ("GET", "/bucket/:id/objects/stream") => Bucket(Read),
+            ("GET", "/bucket/:id/-/export") => Bucket(Read),
+            ("PUT", "/bucket/:id/:name") => Bucket(Write),
+            ("POST", "/bucket/:id/:name") => Store(Write),
+            ("GET", "/bucket/:id/key/*key") => Bucket(Read),
+            ("PUT" | "DELETE", "/bucket/:id/key/*key") => Bucket(Write),
+            ("GET", "/bucket/:id/-/keys") => Bucket(Read),
+            ("PUT", "/bucket/:id/part/:part") => Bucket(Write),
+            ("POST", "/bucket/:id/presign/*key") => Bucket(Write),
+            ("GET", "/buckets") => Store(Read),
+            ("POST", "/object") => Any(Write),
+            ("GET", "/object/:id") => Bucket(Read),
+            ("POST", "/object/:id") => Any(Write),
+            ("GET", "/object/:bucket/:id") => Bucket(Read),
+            // Linking names an object that may be in any bucket, so it
+            // cannot be limited to the buckets of a restricted token.
+            ("POST", "/object/:bucket/:id") => Store(Write),
+            ("PUT" | "DELETE", "/object/:bucket/:id") => Bucket(Write),
+            ("GET", "/object/:bucket/:object/data") => Bucket(Read),
+            ("GET", "/object/:bucket/:object/metadata") => Bucket(Read),
+            ("PATCH", "/object/:bucket/:object/metadata") => Bucket(Write),
+            ("POST", "/object/:bucket/:object/presign") => Bucket(Read),
+            ("GET", "/object/:bucket/all") => Bucket(Read),
+            ("POST", "/object/:bucket/hash/:hash") => Store(Write),
+            ("GET", "/object/errors") => Store(Read),
+            ("GET", "/object/hash/:hash") => Store(Read),
+            ("DELETE", "/objects") => Store(Admin),
+            (_, "/part/:id" | "/part/:id/:offset" | "/part/:id/ranges") => {
+                Any(Write)
+            }
+            ("GET", "/parts") => Store(Read),
+            ("GET", "/status") => Store(Read),
+            _ => Store(Admin),
+        }
+    }
+
+    fn scope(self) -> Scope {
+        match self {
+            Self::Any(scope) | Self::Bucket(scope) | Self::Store(scope) => {
+                scope
+            }
+        }
+    }
+}
+
+fn forbidden(message: String) -> Response {
+    error_response(StatusCode::FORBIDDEN, ErrorCode::Forbidden, message)
+}
+
+fn unauthorized(message: &'static str) -> Response {
+    (
+        [(WWW_AUTHENTICATE, "Bearer")],
+        error_response(
+            StatusCode::UNAUTHORIZED,
+            ErrorCode::Unauthorized,
+            message,
+        ),
+    )
+        .into_response()
+}
+
+/// Returns whether `token` may access the bucket identified by `bucket`,
+/// which is either a bucket ID or name.
+async fn can_access(
+    store: &ObjectStore,
+    token: &Token,
+    bucket: &str,
+) -> Result<bool, Error> {
+    if !token.is_restricted() {
+        return Ok(true);
+    }
+
+    let id = match Uuid::try_parse(bucket) {
+        Ok(id) => id,
+        Err(_) => match store.get_bucket(bucket).await {
+            Ok(bucket) => bucket.id,
+            Err(fstore_core::Error::Sql(sqlx::Error::RowNotFound)) => {
+                return Ok(false)
+            }
+            Err(err) => return Err(err.into()),
+        },
+    };
+
+    Ok(token.can_access(&id))
+}
+
+/// Rejects requests without a bearer token that permits the matched route,
+/// unless they were made with a valid presigned URL.
+#[allow(clippy::too_many_arguments)]
+pub async fn authorize(
+    State(AppState { store }): State<AppState>,
+    method: Method,
+    path: MatchedPath,
+    params: RawPathParams,
+    authorization: Option<TypedHeader<Authorization<Bearer>>>,
+    presigned: Option<Extension<Presigned>>,
+    request: Request,
+    next: Next,
+) -> Response {
+    if presigned.is_some() {
+        return next.run(request).await;
+    }
+
+    let Some(TypedHeader(Authorization(bearer))) = authorization else {
+        return unauthorized("Missing bearer token");
+    };
+
+    let token = match store.authenticate(bearer.token()).await {
+        Ok(Some(token)) => token,
+        Ok(None) => return unauthorized("Invalid token"),
+        Err(err) => return Error::from(err).into_response(),
+    };
+
+    let access = Access::of(&method, path.as_str());
+    let scope = access.scope();
+
+    if !token.permits(scope) {
+        return forbidden(format!(
+            "Token '{}' does not have the '{scope}' scope",
+            token.name
+        ));
+    }
+
+    match access {
+        Access::Any(_) => (),
+        Access::Bucket(_) => {
+            let bucket = params.iter().next().map(|(_, value)| value);
+
+            let allowed = match bucket {
+                Some(bucket) => can_access(&store, &token, bucket).await,
+                None => Ok(!token.is_restricted()),
+            };
+
+            match allowed {
+                Ok(true) => (),
+                Ok(false) => {
+                    return forbidden(format!(
+                        "Token '{}' does not permit access to this bucket",
+                        token.name
+                    ))
+                }
+                Err(err) => return err.into_response(),
+            }
+        }
+        Access::Store(_) => {
+            if token.is_restricted() {
+                return forbidden(format!(
+                    "Token '{}' is restricted to specific buckets",
+                    token.name
+                ));
+            }
+        }
+    }
+
+    next.run(request).await
+}
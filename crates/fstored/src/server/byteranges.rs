@@ -0,0 +1,272 @@
+use axum::{
+    body::{Body, Bytes},
+    http::{header::CONTENT_TYPE, HeaderValue},
+    response::IntoResponseParts,
+};
+use axum_extra::{
+    headers::{ContentLength, Header, Range},
+    TypedHeader,
+};
+use futures_util::stream;
+use std::{collections::VecDeque, io, io::SeekFrom};
+use tokio::{
+    fs::File,
+    io::{AsyncReadExt, AsyncSeekExt},
+};
+use uuid::Uuid;
+
+/// The most ranges served in one response. Requests for more ranges are
+/// served the entire object instead.
+pub const MAX_RANGES: usize = 64;
+
+/// The most bytes of a range read from the file at once.
+const CHUNK_SIZE: u64 = 64 * 1024;
+
+/// Returns the inclusive bounds of a byte range spec, such as `0-99`, `500-`
+/// or `-100`, within content whose last byte is at `last`.
+///
+/// A suffix longer than the content selects all of it (RFC 9110, section
+/// 14.1.2), which `Range::satisfiable_ranges` does not allow for.
+fn bounds(spec: &str, last: u64) -> Option<(u64, u64)> {
+    let (start, end) = spec.trim().split_once('-')?;
+
+    let (start, end) = match (start, end) {
+        ("", suffix) => match suffix.parse::<u64>().ok()? {
+            0 => return None,
+            len => (last.saturating_sub(len - 1), last),
+        },
+        (start, "") => (start.parse().ok()?, last),
+        (start, end) => {
+            (start.parse().ok()?, end.parse::<u64>().ok()?.min(last))
+        }
+    };
+
+    (start <= end).then_some((start, end))
+}
+
+/// Returns the inclusive bounds of the ranges in `range` that lie within
+/// content of `size` bytes, sorted by offset. Overlapping and adjacent
+/// ranges are merged, so that no byte is sent more than once.
+pub fn satisfiable(range: &Range, size: u64) -> Vec<(u64, u64)> {
+    let Some(last) = size.checked_sub(1) else {
+        return Vec::new();
+    };
+
+    let mut values = Vec::new();
+    range.encode(&mut values);
+
+    let mut ranges: Vec<_> = values
+        .iter()
+        .filter_map(|value| value.to_str().ok()?.strip_prefix("bytes="))
+        .flat_map(|specs| specs.split(','))
+        .filter_map(|spec| bounds(spec, last))
+        .collect();
+
+    ranges.sort_unstable();
+
+    let mut merged: Vec<(u64, u64)> = Vec::with_capacity(ranges.len());
+
+    for (start, end) in ranges {
+        match merged.last_mut() {
+            Some((_, previous)) if start <= previous.saturating_add(1) => {
+                *previous = (*previous).max(end);
+            }
+            _ => merged.push((start, end)),
+        }
+    }
+
+    merged
+}
+
+enum Part {
+    /// The delimiter and headers preceding a range, or the final delimiter.
+    Bytes(Bytes),
+
+    /// A range of the file that has yet to be sent.
+    File { offset: u64, remaining: u64 },
+}
+
+struct Multipart {
+    file: File,
+    parts: VecDeque<Part>,
+}
+
+impl Multipart {
+    async fn next_chunk(&mut self) -> Option<io::Result<Bytes>> {
+        loop {
+            match self.parts.front_mut()? {
+                Part::Bytes(_) => {
+                    let Some(Part::Bytes(bytes)) = self.parts.pop_front()
+                    else {
+                        unreachable!();
+                    };
+
+                    return Some(Ok(bytes));
+                }
+                Part::File { remaining: 0, .. } => {
+                    self.parts.pop_front();
+                }
+                Part::File { offset, remaining } => {
+                    let len = (*remaining).min(CHUNK_SIZE);
+                    let result = read_at(&mut self.file, *offset, len).await;
+
+                    if let Ok(bytes) = &result {
+                        *offset += bytes.len() as u64;
+                        *remaining -= bytes.len() as u64;
+                    }
+
+                    return Some(result);
+                }
+            }
+        }
+    }
+}
+
+async fn read_at(file: &mut File, offset: u64, len: u64) -> io::Result<Bytes> {
+    file.seek(SeekFrom::Start(offset)).await?;
+
+    let mut buf = Vec::with_capacity(len as usize);
+    file.take(len).read_to_end(&mut buf).await?;
+
+    if buf.is_empty() {
+        return Err(io::Error::new(
+            io::ErrorKind::UnexpectedEof,
+            "object ended before the requested range",
+        ));
+    }
+
+    Ok(buf.into())
+}
+
+/// Returns the headers and body of a `multipart/byteranges` response
+/// containing each of `ranges` of an object's data as a separate part
+/// (RFC 9110, section 14.6).
+///
+/// Only the parts' headers are held in memory; the ranges are read from
+/// `file` as the body is sent.
+pub fn multipart(
+    file: File,
+    ranges: &[(u64, u64)],
+    size: u64,
+    media_type: &str,
+) -> (impl IntoResponseParts, Body) {
+    let boundary = Uuid::new_v4().simple().to_string();
+    let mut parts = VecDeque::with_capacity(ranges.len() * 2 + 1);
+    let mut length = 0;
+
+    for &(start, end) in ranges {
+        let header = format!(
+            "\r\n--{boundary}\r\n\
+            Content-Type: {media_type}\r\n\
+            Content-Range: bytes {start}-{end}/{size}\r\n\r\n"
+        );
+        let remaining = end - start + 1;
+
+        length += header.len() as u64 + remaining;
+        parts.push_back(Part::Bytes(header.into()));
+        parts.push_back(Part::File {
+            offset: start,
+            remaining,
+        });
+    }
+
+    let trailer = format!("\r\n--{boundary}--\r\n");
+    length += trailer.len() as u64;
+    parts.push_back(Part::Bytes(trailer.into()));
+
+    let content_type = HeaderValue::from_str(&format!(
+        "multipart/byteranges; boundary={boundary}"
+    ))
+    .expect("boundaries are valid header values");
+
+    let body =
+        stream::unfold(Multipart { file, parts }, |mut body| async move {
+            body.next_chunk().await.map(|chunk| (chunk, body))
+        });
+
+    let headers = (
+        [(CONTENT_TYPE, content_type)],
+        TypedHeader(ContentLength(length)),
+    );
+
+    (headers, Body::from_stream(body))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SIZE: u64 = 1000;
+
+    fn parse(value: &str) -> Range {
+        let value = HeaderValue::from_str(value).unwrap();
+        Range::decode(&mut [value].iter()).unwrap()
+    }
+
+    fn ranges(value: &str) -> Vec<(u64, u64)> {
+        satisfiable(&parse(value), SIZE)
+    }
+
+    #[test]
+    fn bounded_range() {
+        assert_eq!(ranges("bytes=100-199"), [(100, 199)]);
+        assert_eq!(ranges("bytes=900-1999"), [(900, 999)]);
+    }
+
+    #[test]
+    fn open_ended_range() {
+        assert_eq!(ranges("bytes=500-"), [(500, 999)]);
+        assert_eq!(ranges("bytes=0-"), [(0, 999)]);
+    }
+
+    #[test]
+    fn suffix_range() {
+        assert_eq!(ranges("bytes=-100"), [(900, 999)]);
+        assert_eq!(ranges("bytes=-2000"), [(0, 999)]);
+    }
+
+    #[test]
+    fn unsatisfiable_range() {
+        assert!(ranges("bytes=1000-").is_empty());
+        assert!(ranges("bytes=1000-1999").is_empty());
+        assert!(satisfiable(&parse("bytes=0-"), 0).is_empty());
+    }
+
+    #[test]
+    fn drops_unsatisfiable_ranges() {
+        assert_eq!(ranges("bytes=2000-2999,0-99"), [(0, 99)]);
+    }
+
+    #[test]
+    fn sorts_ranges() {
+        assert_eq!(
+            ranges("bytes=500-599,0-99,-100"),
+            [(0, 99), (500, 599), (900, 999)]
+        );
+    }
+
+    #[test]
+    fn merges_overlapping_ranges() {
+        assert_eq!(ranges("bytes=0-99,50-149"), [(0, 149)]);
+        assert_eq!(ranges("bytes=0-999,100-199"), [(0, 999)]);
+        assert_eq!(ranges("bytes=-100,0-"), [(0, 999)]);
+    }
+
+    #[test]
+    fn merges_adjacent_ranges() {
+        assert_eq!(ranges("bytes=100-199,0-99"), [(0, 199)]);
+        assert_eq!(ranges("bytes=0-99,101-199"), [(0, 99), (101, 199)]);
+    }
+
+    #[test]
+    fn too_many_ranges() {
+        let disjoint: Vec<_> = (0..=MAX_RANGES as u64)
+            .map(|i| format!("{}-{}", i * 10, i * 10 + 4))
+            .collect();
+        let disjoint = ranges(&format!("bytes={}", disjoint.join(",")));
+        assert_eq!(disjoint.len(), MAX_RANGES + 1);
+
+        let repeated = vec!["0-"; MAX_RANGES * 2].join(",");
+        assert_eq!(ranges(&format!("bytes={repeated}")), [(0, 999)]);
+    }
+}
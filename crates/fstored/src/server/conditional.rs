@@ -0,0 +1,96 @@
+use axum::{
+    async_trait,
+    extract::FromRequestParts,
+    http::{request::Parts, HeaderValue},
+};
+use axum_extra::headers::{
+    ETag, HeaderMapExt, IfModifiedSince, IfNoneMatch, IfRange, LastModified,
+};
+use fstore::Object;
+use std::{convert::Infallible, time::SystemTime};
+
+/// The `Cache-Control` header value sent with object data requested by
+/// object ID.
+#[derive(Clone, Debug)]
+pub struct CacheControl(pub HeaderValue);
+
+/// Validators identifying the current contents of an object (RFC 9110,
+/// section 8.8).
+///
+/// Objects are content-addressed, so their SHA-256 hash is a strong
+/// entity tag.
+#[derive(Debug)]
+pub struct Validators {
+    pub etag: ETag,
+    pub last_modified: LastModified,
+}
+
+impl Validators {
+    pub fn new(object: &Object) -> Self {
+        Self {
+            etag: format!("\"{}\"", object.hash)
+                .parse()
+                .expect("object hashes are valid entity tags"),
+            last_modified: SystemTime::from(object.added).into(),
+        }
+    }
+}
+
+/// The conditional request headers sent with a request for object data.
+///
+/// Malformed headers are ignored, as if they had not been sent.
+#[derive(Debug, Default)]
+pub struct Conditions {
+    if_none_match: Option<IfNoneMatch>,
+    if_modified_since: Option<IfModifiedSince>,
+    if_range: Option<IfRange>,
+}
+
+impl Conditions {
+    /// Returns whether the client's cached copy of the object is current,
+    /// in which case the server responds with `304 Not Modified`.
+    ///
+    /// `If-Modified-Since` is only evaluated if `If-None-Match` is absent.
+    pub fn is_not_modified(&self, validators: &Validators) -> bool {
+        match &self.if_none_match {
+            Some(if_none_match) => {
+                !if_none_match.precondition_passes(&validators.etag)
+            }
+            None => self.if_modified_since.as_ref().is_some_and(|since| {
+                !since.is_modified(validators.last_modified.into())
+            }),
+        }
+    }
+
+    /// Returns whether a requested range may be served. If the `If-Range`
+    /// validator no longer matches the object, the entire object is sent
+    /// instead.
+    pub fn is_range_valid(&self, validators: &Validators) -> bool {
+        match &self.if_range {
+            Some(if_range) => !if_range.is_modified(
+                Some(&validators.etag),
+                Some(&validators.last_modified),
+            ),
+            None => true,
+        }
+    }
+}
+
+#[async_trait]
+impl<S> FromRequestParts<S> for Conditions
+where
+    S: Send + Sync,
+{
+    type Rejection = Infallible;
+
+    async fn from_request_parts(
+        parts: &mut Parts,
+        _state: &S,
+    ) -> Result<Self, Self::Rejection> {
+        Ok(Self {
+            if_none_match: parts.headers.typed_get(),
+            if_modified_since: parts.headers.typed_get(),
+            if_range: parts.headers.typed_get(),
+        })
+    }
+}
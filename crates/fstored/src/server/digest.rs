@@ -0,0 +1,104 @@
+use crate::server::error::error_response;
+
+use axum::{
+    async_trait,
+    extract::FromRequestParts,
+    http::{request::Parts, HeaderName, HeaderValue, StatusCode},
+    response::{IntoResponse, Response},
+};
+use base64ct::{Base64, Encoding};
+use fstore::ErrorCode;
+
+pub const REPR_DIGEST: HeaderName = HeaderName::from_static("repr-digest");
+const SHA256: &str = "sha-256";
+const SHA256_SIZE: usize = 32;
+
+/// The SHA-256 hash a client expects uploaded content to have, taken from
+/// the `sha-256` entry of the `Repr-Digest` header (RFC 9530).
+#[derive(Debug)]
+pub struct ExpectedHash(pub Option<String>);
+
+#[derive(Debug)]
+pub struct ExpectedHashRejection(String);
+
+impl IntoResponse for ExpectedHashRejection {
+    fn into_response(self) -> Response {
+        error_response(
+            StatusCode::BAD_REQUEST,
+            ErrorCode::BadRequest,
+            format!("Invalid {REPR_DIGEST} header: {}", self.0),
+        )
+    }
+}
+
+fn parse_digest(value: &str) -> Result<String, String> {
+    let encoded = value
+        .strip_prefix(':')
+        .and_then(|value| value.strip_suffix(':'))
+        .ok_or_else(|| {
+            format!("expected a colon-delimited byte sequence: '{value}'")
+        })?;
+
+    let bytes = Base64::decode_vec(encoded)
+        .map_err(|err| format!("invalid base64 in '{encoded}': {err}"))?;
+
+    if bytes.len() != SHA256_SIZE {
+        return Err(format!(
+            "expected a {SHA256_SIZE} byte {SHA256} digest, received {} bytes",
+            bytes.len()
+        ));
+    }
+
+    Ok(base16ct::lower::encode_string(&bytes))
+}
+
+/// Returns the `Repr-Digest` header value for content with the given
+/// SHA-256 hash, or `None` if the hash is not a valid hex string.
+pub fn repr_digest(hash: &str) -> Option<HeaderValue> {
+    let bytes = base16ct::mixed::decode_vec(hash).ok()?;
+    let value = format!("{SHA256}=:{}:", Base64::encode_string(&bytes));
+
+    HeaderValue::from_str(&value).ok()
+}
+
+#[async_trait]
+impl<S> FromRequestParts<S> for ExpectedHash
+where
+    S: Send + Sync,
+{
+    type Rejection = ExpectedHashRejection;
+
+    async fn from_request_parts(
+        parts: &mut Parts,
+        _state: &S,
+    ) -> Result<Self, Self::Rejection> {
+        let mut hash = None;
+
+        for value in parts.headers.get_all(REPR_DIGEST) {
+            let value = value
+                .to_str()
+                .map_err(|err| ExpectedHashRejection(err.to_string()))?;
+
+            for member in value.split(',') {
+                let Some((algorithm, digest)) = member.split_once('=') else {
+                    return Err(ExpectedHashRejection(format!(
+                        "expected '<algorithm>=<digest>': '{}'",
+                        member.trim()
+                    )));
+                };
+
+                if !algorithm.trim().eq_ignore_ascii_case(SHA256) {
+                    continue;
+                }
+
+                let digest = digest.split(';').next().unwrap_or_default();
+                hash = Some(
+                    parse_digest(digest.trim())
+                        .map_err(ExpectedHashRejection)?,
+                );
+            }
+        }
+
+        Ok(Self(hash))
+    }
+}
@@ -0,0 +1,77 @@
+use axum::http::{header::CONTENT_DISPOSITION, HeaderName, HeaderValue};
+use fstore::Object;
+use percent_encoding::{utf8_percent_encode, AsciiSet, NON_ALPHANUMERIC};
+use serde::Deserialize;
+
+/// Characters that are percent-encoded in an extended `filename*`
+/// parameter: everything except `attr-char` (RFC 8187, section 3.2.1).
+const ATTR_CHAR: &AsciiSet = &NON_ALPHANUMERIC
+    .remove(b'!')
+    .remove(b'#')
+    .remove(b'$')
+    .remove(b'&')
+    .remove(b'+')
+    .remove(b'-')
+    .remove(b'.')
+    .remove(b'^')
+    .remove(b'_')
+    .remove(b'`')
+    .remove(b'|')
+    .remove(b'~');
+
+/// Whether clients should display object data or save it to a file.
+#[derive(Clone, Copy, Debug, Default, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Disposition {
+    #[default]
+    Inline,
+    Attachment,
+}
+
+impl Disposition {
+    fn as_str(self) -> &'static str {
+        match self {
+            Self::Inline => "inline",
+            Self::Attachment => "attachment",
+        }
+    }
+}
+
+/// Query parameters selecting the disposition of object data, for example
+/// `?disposition=attachment`.
+#[derive(Debug, Default, Deserialize)]
+pub struct DispositionQuery {
+    #[serde(default)]
+    pub disposition: Disposition,
+}
+
+/// Returns the `Content-Disposition` header sent with an object's data
+/// (RFC 6266).
+///
+/// The filename is sent both as a quoted ASCII approximation, for clients
+/// that do not support extended parameters, and as UTF-8.
+pub fn content_disposition(
+    object: &Object,
+    disposition: Disposition,
+) -> [(HeaderName, HeaderValue); 1] {
+    let filename = object
+        .filename
+        .clone()
+        .unwrap_or_else(|| object.default_filename());
+    let fallback: String = filename
+        .chars()
+        .map(|c| match c {
+            ' '..='~' if c != '"' && c != '\\' => c,
+            _ => '_',
+        })
+        .collect();
+    let encoded = utf8_percent_encode(&filename, ATTR_CHAR);
+
+    let value = HeaderValue::from_str(&format!(
+        "{}; filename=\"{fallback}\"; filename*=UTF-8''{encoded}",
+        disposition.as_str()
+    ))
+    .expect("filenames are encoded as valid header values");
+
+    [(CONTENT_DISPOSITION, value)]
+}
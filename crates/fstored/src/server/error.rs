@@ -1,13 +1,19 @@
 use axum::{
     http::StatusCode,
     response::{IntoResponse, Response},
+    Json,
 };
 use axum_range::RangeNotSatisfiable;
+use fstore::{ErrorBody, ErrorCode};
 use log::error;
-use sqlx::error::Error as SqlError;
+use sqlx::{
+    error::{DatabaseError, Error as SqlError},
+    postgres::PgDatabaseError,
+};
 
 pub enum Error {
     Core(fstore_core::Error),
+    PresignDisabled,
     RangeNotSatisfiable(RangeNotSatisfiable),
 }
 
@@ -23,31 +29,159 @@ impl From<RangeNotSatisfiable> for Error {
     }
 }
 
-impl IntoResponse for Error {
-    fn into_response(self) -> Response {
-        use fstore_core::Error::*;
-
-        if let Self::Core(error) = &self {
-            match error {
-                Sql(sql) => match sql {
-                    SqlError::RowNotFound => {
-                        return (StatusCode::NOT_FOUND, "Not found")
-                            .into_response()
-                    }
-                    error => error!("{error}: {sql}"),
-                },
-                NotFound(_) => {
-                    return (StatusCode::NOT_FOUND, format!("{error}"))
-                        .into_response()
+/// Returns an error response with a JSON body containing `code` and
+/// `message`. Messages from the core library, the database and axum's
+/// extractors do not share a casing, so the first letter is capitalized.
+pub fn error_response(
+    status: StatusCode,
+    code: ErrorCode,
+    message: impl Into<String>,
+) -> Response {
+    let body = ErrorBody {
+        code,
+        message: capitalize(message.into()),
+    };
+
+    (status, Json(body)).into_response()
+}
+
+fn capitalize(message: String) -> String {
+    let mut chars = message.chars();
+
+    match chars.next() {
+        Some(first) if first.is_lowercase() => {
+            first.to_uppercase().chain(chars).collect()
+        }
+        _ => message,
+    }
+}
+
+fn internal_error() -> Response {
+    error_response(
+        StatusCode::INTERNAL_SERVER_ERROR,
+        ErrorCode::Internal,
+        "Something went wrong",
+    )
+}
+
+/// Appends the key that violated a constraint, as reported by PostgreSQL,
+/// to `message`.
+fn with_detail(error: &dyn DatabaseError, message: &str) -> String {
+    match error
+        .try_downcast_ref::<PgDatabaseError>()
+        .and_then(PgDatabaseError::detail)
+    {
+        Some(detail) => format!("{message}: {detail}"),
+        None => message.into(),
+    }
+}
+
+/// Maps constraint violations to responses. Constraint names are the ones
+/// named in `data.sql` or generated by PostgreSQL for its tables.
+fn database_error(error: &dyn DatabaseError) -> Option<Response> {
+    let (status, code, message) = match error.constraint() {
+        Some("bucket_name_key") => (
+            StatusCode::CONFLICT,
+            ErrorCode::AlreadyExists,
+            with_detail(error, "A bucket with that name already exists"),
+        ),
+        Some("bucket_object_bucket_id_fkey") => (
+            StatusCode::NOT_FOUND,
+            ErrorCode::NotFound,
+            with_detail(error, "Bucket not found"),
+        ),
+        Some("bucket_object_metadata_size") => (
+            StatusCode::BAD_REQUEST,
+            ErrorCode::InvalidMetadata,
+            "Invalid metadata: metadata must not exceed 8 KiB".into(),
+        ),
+        Some("bucket_object_object_id_fkey") => (
+            StatusCode::NOT_FOUND,
+            ErrorCode::NotFound,
+            with_detail(error, "Object not found"),
+        ),
+        _ if error.is_unique_violation() => (
+            StatusCode::CONFLICT,
+            ErrorCode::AlreadyExists,
+            with_detail(error, error.message()),
+        ),
+        _ if error.is_foreign_key_violation() => (
+            StatusCode::NOT_FOUND,
+            ErrorCode::NotFound,
+            with_detail(error, "Not found"),
+        ),
+        _ => return None,
+    };
+
+    Some(error_response(status, code, message))
+}
+
+fn core_error(error: fstore_core::Error) -> Response {
+    use fstore_core::Error::*;
+
+    let (status, code) = match &error {
+        Sql(SqlError::RowNotFound) => {
+            return error_response(
+                StatusCode::NOT_FOUND,
+                ErrorCode::NotFound,
+                "Not found",
+            )
+        }
+        Sql(sql) => {
+            if let SqlError::Database(db) = sql {
+                if let Some(response) = database_error(db.as_ref()) {
+                    return response;
                 }
-                _ => error!("{error}"),
             }
-        } else if let Self::RangeNotSatisfiable(error) = self {
-            return error.into_response();
+
+            error!("{error}: {sql}");
+            return internal_error();
+        }
+        HashMismatch { .. } => {
+            (StatusCode::BAD_REQUEST, ErrorCode::HashMismatch)
+        }
+        InvalidCursor(_) => (StatusCode::BAD_REQUEST, ErrorCode::InvalidCursor),
+        InvalidFilename(_) => {
+            (StatusCode::BAD_REQUEST, ErrorCode::InvalidFilename)
+        }
+        InvalidKey(_) => (StatusCode::BAD_REQUEST, ErrorCode::InvalidKey),
+        InvalidLabel(_) => (StatusCode::BAD_REQUEST, ErrorCode::InvalidLabel),
+        InvalidMetadata(_) => {
+            (StatusCode::BAD_REQUEST, ErrorCode::InvalidMetadata)
         }
+        InvalidPart(_) => (StatusCode::CONFLICT, ErrorCode::InvalidPart),
+        InProgress => (StatusCode::CONFLICT, ErrorCode::InProgress),
+        NotFound(_) => (StatusCode::NOT_FOUND, ErrorCode::NotFound),
+        WriteLock => (StatusCode::LOCKED, ErrorCode::WriteLocked),
+        Internal(_) => {
+            error!("{error}");
+            return internal_error();
+        }
+    };
+
+    error_response(status, code, error.to_string())
+}
+
+impl IntoResponse for Error {
+    fn into_response(self) -> Response {
+        match self {
+            Self::Core(error) => core_error(error),
+            Self::PresignDisabled => error_response(
+                StatusCode::NOT_IMPLEMENTED,
+                ErrorCode::PresignDisabled,
+                "Presigned URLs are not enabled on this server",
+            ),
+            Self::RangeNotSatisfiable(error) => {
+                // Keep the Content-Range header reporting the object's size.
+                let (parts, _) = error.into_response().into_parts();
+                let body = ErrorBody {
+                    code: ErrorCode::RangeNotSatisfiable,
+                    message: "Requested range not satisfiable".into(),
+                };
 
-        (StatusCode::INTERNAL_SERVER_ERROR, "Something went wrong")
-            .into_response()
+                (parts.status, parts.headers, Json(body)).into_response()
+            }
+        }
     }
 }
 
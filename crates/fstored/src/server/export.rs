@@ -0,0 +1,151 @@
+use async_zip::{
+    tokio::write::ZipFileWriter, Compression, ZipDateTime, ZipEntry,
+    ZipEntryBuilder,
+};
+use axum::body::Body;
+use chrono::{DateTime, Local, Utc};
+use fstore::{ArchiveFormat, Object};
+use fstore_core::ObjectStore;
+use futures_util::{future, stream, StreamExt};
+use log::error;
+use std::{io, sync::Arc};
+use tokio::{
+    fs::File,
+    io::{AsyncReadExt, AsyncWriteExt, DuplexStream, Take},
+};
+use tokio_tar::{EntryType, Header};
+use tokio_util::{compat::FuturesAsyncWriteCompatExt, io::ReaderStream};
+
+/// The name of the archive entry listing the exported objects.
+const MANIFEST: &str = "manifest.json";
+
+/// The number of archive bytes buffered ahead of the response body.
+const BUFFER_SIZE: usize = 64 * 1024;
+
+/// Permissions of the files in an archive.
+const MODE: u16 = 0o644;
+
+/// Returns a response body streaming `objects` as an archive.
+///
+/// The archive begins with a JSON manifest of the objects, followed by
+/// their data in entries named by [`Object::default_filename`]. Entries are
+/// read from the store as the body is sent; if one cannot be read, the body
+/// ends with an error rather than a truncated archive.
+pub fn archive(
+    store: Arc<ObjectStore>,
+    objects: Vec<Object>,
+    format: ArchiveFormat,
+) -> Body {
+    let (writer, reader) = tokio::io::duplex(BUFFER_SIZE);
+
+    let task = tokio::spawn(async move {
+        match format {
+            ArchiveFormat::Tar => write_tar(&store, &objects, writer).await,
+            ArchiveFormat::Zip => write_zip(&store, &objects, writer).await,
+        }
+    });
+
+    let result = stream::once(async move {
+        let result =
+            task.await.unwrap_or_else(|err| Err(io::Error::other(err)));
+
+        result.err().map(|err| {
+            error!("Failed to export objects: {err}");
+            Err(err)
+        })
+    })
+    .filter_map(future::ready);
+
+    Body::from_stream(ReaderStream::new(reader).chain(result))
+}
+
+fn manifest(objects: &[Object]) -> io::Result<Vec<u8>> {
+    Ok(serde_json::to_vec_pretty(objects)?)
+}
+
+async fn open(store: &ObjectStore, object: &Object) -> io::Result<Take<File>> {
+    let file = store.get_object(&object.id).await.map_err(|err| {
+        io::Error::other(format!("failed to open object {}: {err}", object.id))
+    })?;
+
+    Ok(file.take(object.size))
+}
+
+fn tar_header(size: u64, modified: DateTime<Local>) -> Header {
+    let mut header = Header::new_gnu();
+
+    header.set_entry_type(EntryType::Regular);
+    header.set_mode(MODE.into());
+    header.set_mtime(modified.timestamp().try_into().unwrap_or_default());
+    header.set_size(size);
+
+    header
+}
+
+async fn write_tar(
+    store: &ObjectStore,
+    objects: &[Object],
+    writer: DuplexStream,
+) -> io::Result<()> {
+    let mut builder = tokio_tar::Builder::new(writer);
+
+    let manifest = manifest(objects)?;
+    let mut header = tar_header(manifest.len() as u64, Local::now());
+    builder
+        .append_data(&mut header, MANIFEST, manifest.as_slice())
+        .await?;
+
+    for object in objects {
+        let data = open(store, object).await?;
+        let mut header = tar_header(object.size, object.added);
+
+        builder
+            .append_data(&mut header, object.default_filename(), data)
+            .await?;
+    }
+
+    builder.into_inner().await?.shutdown().await
+}
+
+fn zip_entry(name: String, modified: DateTime<Local>) -> ZipEntry {
+    ZipEntryBuilder::new(name.into(), Compression::Stored)
+        .last_modification_date(ZipDateTime::from_chrono(
+            &modified.with_timezone(&Utc),
+        ))
+        .unix_permissions(MODE)
+        .build()
+}
+
+async fn write_zip(
+    store: &ObjectStore,
+    objects: &[Object],
+    writer: DuplexStream,
+) -> io::Result<()> {
+    let mut zip = ZipFileWriter::with_tokio(writer);
+
+    let manifest = manifest(objects)?;
+    zip.write_entry_whole(zip_entry(MANIFEST.into(), Local::now()), &manifest)
+        .await
+        .map_err(io::Error::other)?;
+
+    for object in objects {
+        let mut data = open(store, object).await?;
+        let entry = zip_entry(object.default_filename(), object.added);
+
+        let mut entry = zip
+            .write_entry_stream(entry)
+            .await
+            .map_err(io::Error::other)?
+            .compat_write();
+
+        tokio::io::copy(&mut data, &mut entry).await?;
+        entry.into_inner().close().await.map_err(io::Error::other)?;
+    }
+
+    zip.close()
+        .await
+        .map_err(io::Error::other)?
+        .into_inner()
+        .shutdown()
+        .await
+}
@@ -0,0 +1,136 @@
+use crate::server::error::error_response;
+
+use axum::{
+    async_trait,
+    extract::{
+        rejection::{JsonRejection, PathRejection, QueryRejection},
+        FromRequest, FromRequestParts, Request,
+    },
+    http::{request::Parts, StatusCode},
+    response::{IntoResponse, Response},
+};
+use fstore::ErrorCode;
+use serde::{de::DeserializeOwned, Serialize};
+
+/// A rejection from one of axum's extractors, sent with a JSON error body
+/// instead of plain text. The status of the original rejection is kept.
+#[derive(Debug)]
+pub struct Rejection {
+    status: StatusCode,
+    message: String,
+}
+
+impl IntoResponse for Rejection {
+    fn into_response(self) -> Response {
+        let code = match self.status {
+            StatusCode::UNSUPPORTED_MEDIA_TYPE => {
+                ErrorCode::UnsupportedMediaType
+            }
+            status if status.is_server_error() => ErrorCode::Internal,
+            _ => ErrorCode::BadRequest,
+        };
+
+        error_response(self.status, code, self.message)
+    }
+}
+
+impl From<JsonRejection> for Rejection {
+    fn from(value: JsonRejection) -> Self {
+        Self {
+            status: value.status(),
+            message: value.body_text(),
+        }
+    }
+}
+
+impl From<PathRejection> for Rejection {
+    fn from(value: PathRejection) -> Self {
+        Self {
+            status: value.status(),
+            message: value.body_text(),
+        }
+    }
+}
+
+impl From<QueryRejection> for Rejection {
+    fn from(value: QueryRejection) -> Self {
+        Self {
+            status: value.status(),
+            message: value.body_text(),
+        }
+    }
+}
+
+/// A JSON request body, or a JSON response.
+#[derive(Debug)]
+pub struct Json<T>(pub T);
+
+#[async_trait]
+impl<T, S> FromRequest<S> for Json<T>
+where
+    T: DeserializeOwned,
+    S: Send + Sync,
+{
+    type Rejection = Rejection;
+
+    async fn from_request(
+        req: Request,
+        state: &S,
+    ) -> Result<Self, Self::Rejection> {
+        let axum::Json(value) = axum::Json::from_request(req, state).await?;
+        Ok(Self(value))
+    }
+}
+
+impl<T> IntoResponse for Json<T>
+where
+    T: Serialize,
+{
+    fn into_response(self) -> Response {
+        axum::Json(self.0).into_response()
+    }
+}
+
+/// Parameters taken from the request path.
+#[derive(Debug)]
+pub struct Path<T>(pub T);
+
+#[async_trait]
+impl<T, S> FromRequestParts<S> for Path<T>
+where
+    T: DeserializeOwned + Send,
+    S: Send + Sync,
+{
+    type Rejection = Rejection;
+
+    async fn from_request_parts(
+        parts: &mut Parts,
+        state: &S,
+    ) -> Result<Self, Self::Rejection> {
+        let axum::extract::Path(value) =
+            axum::extract::Path::from_request_parts(parts, state).await?;
+        Ok(Self(value))
+    }
+}
+
+/// Parameters taken from the request's query string.
+#[derive(Debug)]
+pub struct Query<T>(pub T);
+
+#[async_trait]
+impl<T, S> FromRequestParts<S> for Query<T>
+where
+    T: DeserializeOwned,
+    S: Send + Sync,
+{
+    type Rejection = Rejection;
+
+    async fn from_request_parts(
+        parts: &mut Parts,
+        state: &S,
+    ) -> Result<Self, Self::Rejection> {
+        let axum::extract::Query(value) =
+            axum::extract::Query::from_request_parts(parts, state).await?;
+        Ok(Self(value))
+    }
+}
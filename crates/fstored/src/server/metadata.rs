@@ -0,0 +1,218 @@
+use crate::server::{
+    digest::{repr_digest, REPR_DIGEST},
+    error::error_response,
+};
+
+use axum::{
+    async_trait,
+    extract::FromRequestParts,
+    http::{request::Parts, HeaderName, HeaderValue, StatusCode},
+    response::{IntoResponse, IntoResponseParts, Response, ResponseParts},
+};
+use fstore::{ErrorCode, Metadata, Object};
+use percent_encoding::{
+    percent_decode_str, utf8_percent_encode, AsciiSet, CONTROLS,
+};
+use std::convert::Infallible;
+
+const METADATA_PREFIX: &str = "x-fstore-meta-";
+
+const ADDED: HeaderName = HeaderName::from_static("x-fstore-added");
+const FILENAME: HeaderName = HeaderName::from_static("x-fstore-filename");
+const KEY: HeaderName = HeaderName::from_static("x-fstore-key");
+const OBJECT_ID: HeaderName = HeaderName::from_static("x-fstore-object-id");
+const SIZE: HeaderName = HeaderName::from_static("x-fstore-size");
+
+/// Characters that are percent-encoded in header values: control
+/// characters, which header values cannot contain, and the percent sign.
+/// Non-ASCII characters are always encoded.
+const ENCODED: &AsciiSet = &CONTROLS.add(b'%');
+
+fn encode(value: &str) -> HeaderValue {
+    HeaderValue::from_str(&utf8_percent_encode(value, ENCODED).to_string())
+        .expect("percent-encoded values are valid header values")
+}
+
+/// The size in bytes a client expects uploaded content to have, taken from
+/// the `X-Fstore-Size` request header.
+#[derive(Debug, Default)]
+pub struct ExpectedSize(pub Option<u64>);
+
+#[derive(Debug)]
+pub struct ExpectedSizeRejection(String);
+
+impl IntoResponse for ExpectedSizeRejection {
+    fn into_response(self) -> Response {
+        error_response(
+            StatusCode::BAD_REQUEST,
+            ErrorCode::BadRequest,
+            format!("Invalid {SIZE} header: {}", self.0),
+        )
+    }
+}
+
+#[async_trait]
+impl<S> FromRequestParts<S> for ExpectedSize
+where
+    S: Send + Sync,
+{
+    type Rejection = ExpectedSizeRejection;
+
+    async fn from_request_parts(
+        parts: &mut Parts,
+        _state: &S,
+    ) -> Result<Self, Self::Rejection> {
+        let Some(value) = parts.headers.get(SIZE) else {
+            return Ok(Self(None));
+        };
+
+        let size = value
+            .to_str()
+            .map_err(|err| ExpectedSizeRejection(err.to_string()))?
+            .parse()
+            .map_err(|err: std::num::ParseIntError| {
+                ExpectedSizeRejection(err.to_string())
+            })?;
+
+        Ok(Self(Some(size)))
+    }
+}
+
+/// The name of the file an object was uploaded from, taken from the
+/// percent-encoded `X-Fstore-Filename` request header.
+#[derive(Debug, Default)]
+pub struct Filename(pub Option<String>);
+
+#[derive(Debug)]
+pub struct FilenameRejection(String);
+
+impl IntoResponse for FilenameRejection {
+    fn into_response(self) -> Response {
+        error_response(
+            StatusCode::BAD_REQUEST,
+            ErrorCode::InvalidFilename,
+            format!("Invalid filename header: {}", self.0),
+        )
+    }
+}
+
+#[async_trait]
+impl<S> FromRequestParts<S> for Filename
+where
+    S: Send + Sync,
+{
+    type Rejection = FilenameRejection;
+
+    async fn from_request_parts(
+        parts: &mut Parts,
+        _state: &S,
+    ) -> Result<Self, Self::Rejection> {
+        let Some(value) = parts.headers.get(FILENAME) else {
+            return Ok(Self(None));
+        };
+
+        let value = value
+            .to_str()
+            .map_err(|err| FilenameRejection(err.to_string()))?;
+        let filename = percent_decode_str(value)
+            .decode_utf8()
+            .map_err(|err| FilenameRejection(err.to_string()))?;
+
+        Ok(Self(Some(filename.into_owned())))
+    }
+}
+
+/// User-defined object metadata, taken from request headers named
+/// `X-Fstore-Meta-<name>`.
+///
+/// Header names are case-insensitive, so metadata names are lowercased.
+/// Values must be valid UTF-8.
+#[derive(Debug, Default)]
+pub struct UserMetadata(pub Metadata);
+
+#[derive(Debug)]
+pub struct UserMetadataRejection(String);
+
+impl IntoResponse for UserMetadataRejection {
+    fn into_response(self) -> Response {
+        error_response(
+            StatusCode::BAD_REQUEST,
+            ErrorCode::InvalidMetadata,
+            format!("Invalid metadata header: {}", self.0),
+        )
+    }
+}
+
+#[async_trait]
+impl<S> FromRequestParts<S> for UserMetadata
+where
+    S: Send + Sync,
+{
+    type Rejection = UserMetadataRejection;
+
+    async fn from_request_parts(
+        parts: &mut Parts,
+        _state: &S,
+    ) -> Result<Self, Self::Rejection> {
+        let mut metadata = Metadata::new();
+
+        for (name, value) in &parts.headers {
+            let Some(name) = name.as_str().strip_prefix(METADATA_PREFIX) else {
+                continue;
+            };
+
+            let value =
+                std::str::from_utf8(value.as_bytes()).map_err(|err| {
+                    UserMetadataRejection(format!("'{name}': {err}"))
+                })?;
+
+            metadata.insert(name.to_owned(), value.to_owned());
+        }
+
+        Ok(Self(metadata))
+    }
+}
+
+/// Headers describing an object in a bucket, sent with its data so that
+/// clients can read the object without a separate request.
+///
+/// Keys, filenames and metadata values are percent-encoded. Metadata whose names are
+/// not valid header names is omitted.
+pub struct ObjectHeaders<'a>(pub &'a Object);
+
+impl IntoResponseParts for ObjectHeaders<'_> {
+    type Error = Infallible;
+
+    fn into_response_parts(
+        self,
+        mut res: ResponseParts,
+    ) -> Result<ResponseParts, Self::Error> {
+        let object = self.0;
+        let headers = res.headers_mut();
+
+        headers.insert(OBJECT_ID, encode(&object.id.to_string()));
+        headers.insert(ADDED, encode(&object.added.to_rfc3339()));
+
+        if let Some(digest) = repr_digest(&object.hash) {
+            headers.insert(REPR_DIGEST, digest);
+        }
+
+        if let Some(key) = &object.key {
+            headers.insert(KEY, encode(key));
+        }
+
+        if let Some(filename) = &object.filename {
+            headers.insert(FILENAME, encode(filename));
+        }
+
+        for (name, value) in &object.metadata {
+            let name = format!("{METADATA_PREFIX}{name}");
+
+            if let Ok(name) = HeaderName::from_bytes(name.as_bytes()) {
+                headers.insert(name, encode(value));
+            }
+        }
+
+        Ok(res)
+    }
+}
@@ -0,0 +1,173 @@
+use crate::server::error::error_response;
+
+use axum::{
+    extract::{MatchedPath, Query, RawPathParams, Request, State},
+    http::{Method, StatusCode},
+    middleware::Next,
+    response::Response,
+};
+use fstore::{ErrorCode, Signature};
+use hmac::{Hmac, Mac};
+use serde::Deserialize;
+use sha2::Sha256;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+/// The route serving an object's data, which presigned GET URLs grant
+/// access to.
+pub const OBJECT_DATA: &str = "/object/:bucket/:object/data";
+
+/// The route storing an object under a key, which presigned PUT URLs grant
+/// access to.
+pub const KEY: &str = "/bucket/:id/key/*key";
+
+const DEFAULT_EXPIRY: Duration = Duration::from_secs(60 * 60);
+const MAX_EXPIRY: Duration = Duration::from_secs(7 * 24 * 60 * 60);
+
+fn now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_secs()
+}
+
+/// Marks a request as authorized by a valid presigned URL.
+#[derive(Clone, Copy, Debug)]
+pub struct Presigned;
+
+#[derive(Debug, Deserialize)]
+pub struct PresignQuery {
+    /// Seconds until the URL expires, at most seven days.
+    expires_in: Option<u64>,
+}
+
+impl PresignQuery {
+    fn expires(&self) -> u64 {
+        let expires_in = self
+            .expires_in
+            .map(Duration::from_secs)
+            .unwrap_or(DEFAULT_EXPIRY)
+            .min(MAX_EXPIRY);
+
+        now() + expires_in.as_secs()
+    }
+}
+
+#[derive(Debug, Deserialize)]
+pub struct SignedQuery {
+    expires: u64,
+    signature: String,
+}
+
+/// Signs and verifies URLs that grant temporary access to a single route
+/// for one bucket and object or key.
+#[derive(Clone)]
+pub struct Signer {
+    mac: Hmac<Sha256>,
+}
+
+impl Signer {
+    pub fn new(key: &str) -> Self {
+        Self {
+            mac: Hmac::new_from_slice(key.as_bytes())
+                .expect("HMAC accepts keys of any size"),
+        }
+    }
+
+    fn mac(
+        &self,
+        method: &Method,
+        route: &str,
+        bucket: &str,
+        object: &str,
+        expires: u64,
+    ) -> Hmac<Sha256> {
+        let mut mac = self.mac.clone();
+        mac.update(
+            format!("{method}\n{route}\n{bucket}\n{object}\n{expires}")
+                .as_bytes(),
+        );
+        mac
+    }
+
+    /// Returns a signature permitting `method` requests to `route` for the
+    /// given bucket and object or key until the requested expiry.
+    pub fn sign(
+        &self,
+        method: Method,
+        route: &str,
+        bucket: &str,
+        object: &str,
+        query: &PresignQuery,
+    ) -> Signature {
+        let expires = query.expires();
+        let signature = self
+            .mac(&method, route, bucket, object, expires)
+            .finalize()
+            .into_bytes();
+
+        Signature {
+            expires,
+            signature: base16ct::lower::encode_string(&signature),
+        }
+    }
+
+    fn verify(
+        &self,
+        method: &Method,
+        route: &str,
+        bucket: &str,
+        object: &str,
+        query: &SignedQuery,
+    ) -> bool {
+        let Ok(signature) = base16ct::mixed::decode_vec(&query.signature)
+        else {
+            return false;
+        };
+
+        self.mac(method, route, bucket, object, query.expires)
+            .verify_slice(&signature)
+            .is_ok()
+    }
+}
+
+fn forbidden(message: &'static str) -> Response {
+    error_response(StatusCode::FORBIDDEN, ErrorCode::Forbidden, message)
+}
+
+/// Checks the signature of requests made with a presigned URL, marking
+/// valid ones as [`Presigned`]. Requests without a signature are passed on
+/// unchanged.
+pub async fn verify(
+    State(signer): State<Signer>,
+    method: Method,
+    path: MatchedPath,
+    params: RawPathParams,
+    query: Option<Query<SignedQuery>>,
+    mut request: Request,
+    next: Next,
+) -> Response {
+    let Some(Query(query)) = query else {
+        return next.run(request).await;
+    };
+
+    let method = match method == Method::HEAD {
+        true => Method::GET,
+        false => method,
+    };
+
+    let mut params = params.iter().map(|(_, value)| value);
+    let (Some(bucket), Some(object)) = (params.next(), params.next()) else {
+        return forbidden("Invalid signature");
+    };
+
+    if query.expires < now() {
+        return forbidden("URL has expired");
+    }
+
+    if !signer.verify(&method, path.as_str(), bucket, object, &query) {
+        return forbidden("Invalid signature");
+    }
+
+    request.extensions_mut().insert(Presigned);
+    next.run(request).await
+}
@@ -1,26 +1,52 @@
+use crate::server::byteranges;
+use crate::server::conditional::{CacheControl, Conditions, Validators};
+use crate::server::digest::ExpectedHash;
+use crate::server::disposition::{
+    content_disposition, Disposition, DispositionQuery,
+};
+use crate::server::error::error_response;
+use crate::server::error::Error;
 use crate::server::error::Result;
+use crate::server::export;
+use crate::server::extract::{Json, Path, Query};
+use crate::server::metadata::{
+    ExpectedSize, Filename, ObjectHeaders, UserMetadata,
+};
+use crate::server::presign::{self, PresignQuery, Signer};
 use crate::server::AppState;
 
 use axum::{
     async_trait,
-    body::Bytes,
-    extract::{rejection::BytesRejection, FromRequest, Path, Request, State},
-    http::{header::CONTENT_TYPE, StatusCode},
-    response::{IntoResponse, Response},
+    body::{Body, Bytes},
+    extract::{rejection::BytesRejection, FromRequest, Request, State},
+    http::{
+        header::{CACHE_CONTROL, CONTENT_DISPOSITION, CONTENT_TYPE},
+        Method, StatusCode,
+    },
+    response::{IntoResponse, IntoResponseParts, Response},
     routing::{delete, get, post, put},
-    Json, Router,
+    Extension, Router,
 };
 use axum_extra::{
     headers::{AcceptRanges, ContentLength, ContentType, Range},
     TypedHeader,
 };
 use axum_range::{KnownSize, Ranged, RangedResponse};
-use fstore::{Bucket, Object, ObjectError, RemoveResult, StoreTotals};
-use fstore_core::About;
+use fstore::{
+    ArchiveFormat, Bucket, BucketPatch, ErrorCode, KeyList, LabelSelector,
+    Metadata, MetadataPatch, Object, ObjectError, ObjectPage, ObjectQuery,
+    Part, PartRange, RemoveResult, Signature, StoreTotals,
+};
+use fstore_core::{About, ObjectStore};
+use futures_util::StreamExt;
+use log::error;
 use mime::Mime;
-use serde::Serialize;
+use serde::{Deserialize, Serialize};
+use std::io;
 use uuid::Uuid;
 
+const NDJSON: &str = "application/x-ndjson";
+
 #[derive(Debug)]
 struct IdList(Vec<Uuid>);
 
@@ -35,27 +61,30 @@ enum IdListRejection {
 impl IntoResponse for IdListRejection {
     fn into_response(self) -> Response {
         match self {
-            Self::BytesRejection(err) => (
+            Self::BytesRejection(err) => error_response(
                 StatusCode::BAD_REQUEST,
+                ErrorCode::BadRequest,
                 format!("Failed to read request body: {err}"),
             ),
-            Self::InvalidUtf8(err) => (
+            Self::InvalidUtf8(err) => error_response(
                 StatusCode::BAD_REQUEST,
+                ErrorCode::BadRequest,
                 format!("Invalid UTF-8 in request body: {err}"),
             ),
-            Self::InvalidUuid(err) => (
+            Self::InvalidUuid(err) => error_response(
                 StatusCode::BAD_REQUEST,
+                ErrorCode::BadRequest,
                 format!("Invalid UUID in request body: {err}"),
             ),
-            Self::MissingIdListContentType(err) => (
+            Self::MissingIdListContentType(err) => error_response(
                 StatusCode::UNSUPPORTED_MEDIA_TYPE,
+                ErrorCode::UnsupportedMediaType,
                 format!(
                     "Expected content of type `{}`: {err}",
                     mime::TEXT_PLAIN_UTF_8
                 ),
             ),
         }
-        .into_response()
     }
 }
 
@@ -122,6 +151,25 @@ where
     }
 }
 
+#[derive(Debug, Deserialize)]
+struct BucketQuery {
+    #[serde(default)]
+    labels: LabelSelector,
+}
+
+#[derive(Debug, Deserialize)]
+struct ExportQuery {
+    #[serde(default)]
+    format: ArchiveFormat,
+}
+
+#[derive(Debug, Deserialize)]
+struct KeyQuery {
+    #[serde(default)]
+    prefix: String,
+    delimiter: Option<String>,
+}
+
 #[derive(Debug, Serialize)]
 struct NewPart {
     id: Uuid,
@@ -142,6 +190,10 @@ async fn add_bucket(
 async fn add_object(
     State(AppState { store }): State<AppState>,
     Path(bucket): Path<Uuid>,
+    ExpectedHash(hash): ExpectedHash,
+    ExpectedSize(size): ExpectedSize,
+    UserMetadata(metadata): UserMetadata,
+    Filename(filename): Filename,
     request: Request,
 ) -> Result<Json<Object>> {
     let mut part = store.get_part(None).await?;
@@ -149,7 +201,20 @@ async fn add_object(
     part.stream_to_file(request.into_body().into_data_stream())
         .await?;
 
-    let object = store.commit_part(&bucket, part.id()).await?;
+    let id = *part.id();
+    drop(part);
+
+    let object = store
+        .commit_part(
+            &bucket,
+            &id,
+            hash.as_deref(),
+            size,
+            None,
+            &metadata,
+            filename.as_deref(),
+        )
+        .await?;
 
     Ok(Json(object))
 }
@@ -175,25 +240,96 @@ async fn clone_bucket(
     Ok(Json(store.clone_bucket(id, &name).await?))
 }
 
-async fn commit_part(
+#[allow(clippy::too_many_arguments)]
+async fn commit_bucket_part(
     State(AppState { store }): State<AppState>,
-    Path((bucket, id)): Path<(String, Uuid)>,
+    Path((bucket, id)): Path<(Uuid, Uuid)>,
     content_length: Option<TypedHeader<ContentLength>>,
+    ExpectedHash(hash): ExpectedHash,
+    ExpectedSize(size): ExpectedSize,
+    UserMetadata(metadata): UserMetadata,
+    Filename(filename): Filename,
     request: Request,
 ) -> Result<Json<Object>> {
-    let bucket = store.get_bucket(&bucket).await?;
-
-    if let Some(TypedHeader(ContentLength(_content_length))) = content_length {
+    if let Some(TypedHeader(ContentLength(1..))) = content_length {
         let mut part = store.get_part(Some(&id)).await?;
         part.stream_to_file(request.into_body().into_data_stream())
             .await?;
     }
 
-    let object = store.commit_part(&bucket.id, &id).await?;
+    let object = store
+        .commit_part(
+            &bucket,
+            &id,
+            hash.as_deref(),
+            size,
+            None,
+            &metadata,
+            filename.as_deref(),
+        )
+        .await?;
 
     Ok(Json(object))
 }
 
+#[allow(clippy::too_many_arguments)]
+async fn commit_part(
+    State(state): State<AppState>,
+    Path((bucket, id)): Path<(String, Uuid)>,
+    content_length: Option<TypedHeader<ContentLength>>,
+    hash: ExpectedHash,
+    size: ExpectedSize,
+    metadata: UserMetadata,
+    filename: Filename,
+    request: Request,
+) -> Result<Json<Object>> {
+    let bucket = state.store.get_bucket(&bucket).await?.id;
+
+    commit_bucket_part(
+        State(state),
+        Path((bucket, id)),
+        content_length,
+        hash,
+        size,
+        metadata,
+        filename,
+        request,
+    )
+    .await
+}
+
+/// Streams the objects in a bucket as an archive. If the request has a
+/// body, only the objects it lists are exported.
+async fn export_bucket(
+    State(AppState { store }): State<AppState>,
+    Path(bucket): Path<Uuid>,
+    Query(ExportQuery { format }): Query<ExportQuery>,
+    request: Request,
+) -> Result<Response> {
+    let objects = if request.headers().contains_key(CONTENT_TYPE) {
+        match IdList::from_request(request, &()).await {
+            Ok(IdList(objects)) => Some(objects),
+            Err(rejection) => return Ok(rejection.into_response()),
+        }
+    } else {
+        None
+    };
+
+    let objects = store
+        .get_bucket_contents(bucket, objects.as_deref())
+        .await?;
+
+    let headers = [
+        (CONTENT_TYPE, format.media_type().to_owned()),
+        (
+            CONTENT_DISPOSITION,
+            format!("attachment; filename=\"{bucket}.{}\"", format.extension()),
+        ),
+    ];
+
+    Ok((headers, export::archive(store, objects, format)).into_response())
+}
+
 async fn get_all_objects(
     State(AppState { store }): State<AppState>,
     Path(id): Path<Uuid>,
@@ -210,19 +346,166 @@ async fn get_bucket(
 
 async fn get_buckets(
     State(AppState { store }): State<AppState>,
+    Query(BucketQuery { labels }): Query<BucketQuery>,
 ) -> Result<Json<Vec<Bucket>>> {
-    Ok(Json(store.get_buckets().await?))
+    Ok(Json(store.get_buckets(&labels).await?))
+}
+
+async fn get_key(
+    State(AppState { store }): State<AppState>,
+    Path((bucket, key)): Path<(Uuid, String)>,
+    Query(DispositionQuery { disposition }): Query<DispositionQuery>,
+    conditions: Conditions,
+    range: Option<TypedHeader<Range>>,
+) -> Result<Response> {
+    // The object stored under a key can change, so the configured
+    // Cache-Control header is only sent for content-addressed routes.
+    let object = store.get_object_by_key(bucket, &key).await?;
+    object_response(&store, &object, conditions, range, disposition, None).await
+}
+
+async fn get_keys(
+    State(AppState { store }): State<AppState>,
+    Path(bucket): Path<Uuid>,
+    Query(query): Query<KeyQuery>,
+) -> Result<Json<KeyList>> {
+    let delimiter = query.delimiter.as_deref().filter(|d| !d.is_empty());
+    Ok(Json(
+        store.get_keys(bucket, &query.prefix, delimiter).await?,
+    ))
+}
+
+async fn get_object_by_hash(
+    State(AppState { store }): State<AppState>,
+    Path(hash): Path<String>,
+) -> Result<Json<Object>> {
+    Ok(Json(store.get_object_by_hash(&hash).await?))
 }
 
 async fn get_object_data(
     State(AppState { store }): State<AppState>,
     Path((bucket, object)): Path<(Uuid, Uuid)>,
+    Query(DispositionQuery { disposition }): Query<DispositionQuery>,
+    conditions: Conditions,
     range: Option<TypedHeader<Range>>,
+    cache_control: Option<Extension<CacheControl>>,
 ) -> Result<Response> {
     let object = store.get_object_metadata(bucket, object).await?;
+    object_response(
+        &store,
+        &object,
+        conditions,
+        range,
+        disposition,
+        cache_control,
+    )
+    .await
+}
+
+/// Returns the headers sent with an object's data, in response to HEAD
+/// requests, and with `304 Not Modified`.
+fn object_headers<'a>(
+    object: &'a Object,
+    validators: &Validators,
+    disposition: Disposition,
+    cache_control: Option<Extension<CacheControl>>,
+) -> impl IntoResponseParts + 'a {
+    let accept_ranges = TypedHeader(AcceptRanges::bytes());
+    let content_type =
+        TypedHeader(object.media_type().parse::<ContentType>().unwrap());
+    let etag = TypedHeader(validators.etag.clone());
+    let last_modified = TypedHeader(validators.last_modified);
+    let cache_control = cache_control
+        .map(|Extension(CacheControl(value))| [(CACHE_CONTROL, value)]);
+
+    (
+        accept_ranges,
+        content_type,
+        content_disposition(object, disposition),
+        etag,
+        last_modified,
+        cache_control,
+        ObjectHeaders(object),
+    )
+}
+
+/// Responds to a request for an object's data with its headers only.
+fn object_head(
+    object: &Object,
+    conditions: &Conditions,
+    disposition: Disposition,
+    cache_control: Option<Extension<CacheControl>>,
+) -> Response {
+    let validators = Validators::new(object);
+    let headers =
+        object_headers(object, &validators, disposition, cache_control);
+
+    if conditions.is_not_modified(&validators) {
+        return (StatusCode::NOT_MODIFIED, headers, ()).into_response();
+    }
+
+    let content_length = TypedHeader(ContentLength(object.size));
+    (headers, content_length, ()).into_response()
+}
+
+async fn object_response(
+    store: &ObjectStore,
+    object: &Object,
+    conditions: Conditions,
+    range: Option<TypedHeader<Range>>,
+    disposition: Disposition,
+    cache_control: Option<Extension<CacheControl>>,
+) -> Result<Response> {
+    let validators = Validators::new(object);
+
+    if conditions.is_not_modified(&validators) {
+        return Ok(object_head(
+            object,
+            &conditions,
+            disposition,
+            cache_control,
+        ));
+    }
+
+    let mut range = range
+        .map(|TypedHeader(range)| range)
+        .filter(|_| conditions.is_range_valid(&validators));
+
     let file = store.get_object(&object.id).await?;
 
-    let range = range.map(|TypedHeader(range)| range);
+    if let Some(requested) = &range {
+        let ranges = byteranges::satisfiable(requested, object.size);
+
+        match ranges.len() {
+            // Let `Ranged` respond with 416 Range Not Satisfiable.
+            0 => (),
+            1 => {
+                let (start, end) = ranges[0];
+                range = Range::bytes(start..=end).ok();
+            }
+            len if len > byteranges::MAX_RANGES => range = None,
+            _ => {
+                let headers = object_headers(
+                    object,
+                    &validators,
+                    disposition,
+                    cache_control,
+                );
+                let (multipart, body) = byteranges::multipart(
+                    file,
+                    &ranges,
+                    object.size,
+                    &object.media_type(),
+                );
+
+                // The multipart headers replace the object's content type.
+                let response =
+                    (StatusCode::PARTIAL_CONTENT, headers, multipart, body);
+                return Ok(response.into_response());
+            }
+        }
+    }
+
     let body = KnownSize::sized(file, object.size);
 
     let RangedResponse {
@@ -231,11 +514,8 @@ async fn get_object_data(
         stream,
     } = Ranged::new(range, body).try_respond()?;
 
-    let accept_ranges = TypedHeader(AcceptRanges::bytes());
     let content_length = TypedHeader(content_length);
     let content_range = content_range.map(TypedHeader);
-    let content_type =
-        TypedHeader(object.media_type().parse::<ContentType>().unwrap());
 
     let status = if content_range.is_some() {
         StatusCode::PARTIAL_CONTENT
@@ -245,8 +525,7 @@ async fn get_object_data(
 
     let response = (
         status,
-        accept_ranges,
-        content_type,
+        object_headers(object, &validators, disposition, cache_control),
         content_length,
         content_range,
         stream,
@@ -269,6 +548,14 @@ async fn get_object_metadata(
     Ok(Json(object))
 }
 
+async fn get_object_page(
+    State(AppState { store }): State<AppState>,
+    Path(bucket_id): Path<Uuid>,
+    Query(query): Query<ObjectQuery>,
+) -> Result<Json<ObjectPage>> {
+    Ok(Json(store.get_object_page(bucket_id, &query).await?))
+}
+
 async fn get_objects(
     State(AppState { store }): State<AppState>,
     Path(bucket_id): Path<Uuid>,
@@ -278,6 +565,73 @@ async fn get_objects(
     Ok(Json(objects))
 }
 
+async fn get_part(
+    State(AppState { store }): State<AppState>,
+    Path(id): Path<Uuid>,
+) -> Result<Json<Part>> {
+    Ok(Json(store.get_part_info(&id).await?))
+}
+
+async fn get_part_ranges(
+    State(AppState { store }): State<AppState>,
+    Path(id): Path<Uuid>,
+) -> Result<Json<Vec<PartRange>>> {
+    Ok(Json(store.get_part_ranges(&id).await?))
+}
+
+async fn get_parts(
+    State(AppState { store }): State<AppState>,
+) -> Result<Json<Vec<Part>>> {
+    Ok(Json(store.get_parts().await?))
+}
+
+async fn get_user_metadata(
+    State(AppState { store }): State<AppState>,
+    Path((bucket, object)): Path<(Uuid, Uuid)>,
+) -> Result<Json<Metadata>> {
+    Ok(Json(store.get_user_metadata(bucket, object).await?))
+}
+
+async fn head_key(
+    State(AppState { store }): State<AppState>,
+    Path((bucket, key)): Path<(Uuid, String)>,
+    Query(DispositionQuery { disposition }): Query<DispositionQuery>,
+    conditions: Conditions,
+) -> Result<Response> {
+    let object = store.get_object_by_key(bucket, &key).await?;
+    Ok(object_head(&object, &conditions, disposition, None))
+}
+
+async fn head_object_data(
+    State(AppState { store }): State<AppState>,
+    Path((bucket, object)): Path<(Uuid, Uuid)>,
+    Query(DispositionQuery { disposition }): Query<DispositionQuery>,
+    conditions: Conditions,
+    cache_control: Option<Extension<CacheControl>>,
+) -> Result<Response> {
+    let object = store.get_object_metadata(bucket, object).await?;
+    Ok(object_head(
+        &object,
+        &conditions,
+        disposition,
+        cache_control,
+    ))
+}
+
+async fn link_object(
+    State(AppState { store }): State<AppState>,
+    Path((bucket, object)): Path<(Uuid, Uuid)>,
+) -> Result<Json<Object>> {
+    Ok(Json(store.link_object(&bucket, &object).await?))
+}
+
+async fn link_object_by_hash(
+    State(AppState { store }): State<AppState>,
+    Path((bucket, hash)): Path<(Uuid, String)>,
+) -> Result<Json<Object>> {
+    Ok(Json(store.link_object_by_hash(&bucket, &hash).await?))
+}
+
 async fn new_part(
     State(AppState { store }): State<AppState>,
     request: Request,
@@ -294,6 +648,77 @@ async fn new_part(
     }))
 }
 
+async fn put_key(
+    State(AppState { store }): State<AppState>,
+    Path((bucket, key)): Path<(Uuid, String)>,
+    ExpectedHash(hash): ExpectedHash,
+    ExpectedSize(size): ExpectedSize,
+    UserMetadata(metadata): UserMetadata,
+    Filename(filename): Filename,
+    request: Request,
+) -> Result<Json<Object>> {
+    let mut part = store.get_part(None).await?;
+
+    part.stream_to_file(request.into_body().into_data_stream())
+        .await?;
+
+    let id = *part.id();
+    drop(part);
+
+    let object = store
+        .commit_part(
+            &bucket,
+            &id,
+            hash.as_deref(),
+            size,
+            Some(&key),
+            &metadata,
+            filename.as_deref(),
+        )
+        .await?;
+
+    Ok(Json(object))
+}
+
+async fn presign_key(
+    Path((bucket, key)): Path<(Uuid, String)>,
+    Query(query): Query<PresignQuery>,
+    signer: Option<Extension<Signer>>,
+) -> Result<Json<Signature>> {
+    let Some(Extension(signer)) = signer else {
+        return Err(Error::PresignDisabled);
+    };
+
+    Ok(Json(signer.sign(
+        Method::PUT,
+        presign::KEY,
+        &bucket.to_string(),
+        &key,
+        &query,
+    )))
+}
+
+async fn presign_object(
+    State(AppState { store }): State<AppState>,
+    Path((bucket, object)): Path<(Uuid, Uuid)>,
+    Query(query): Query<PresignQuery>,
+    signer: Option<Extension<Signer>>,
+) -> Result<Json<Signature>> {
+    let Some(Extension(signer)) = signer else {
+        return Err(Error::PresignDisabled);
+    };
+
+    store.get_object_metadata(bucket, object).await?;
+
+    Ok(Json(signer.sign(
+        Method::GET,
+        presign::OBJECT_DATA,
+        &bucket.to_string(),
+        &object.to_string(),
+        &query,
+    )))
+}
+
 async fn prune(
     State(AppState { store }): State<AppState>,
 ) -> Result<Json<Vec<Object>>> {
@@ -315,6 +740,13 @@ async fn remove_object(
     Ok(Json(store.remove_object(&bucket, &object).await?))
 }
 
+async fn remove_key(
+    State(AppState { store }): State<AppState>,
+    Path((bucket, key)): Path<(Uuid, String)>,
+) -> Result<Json<Object>> {
+    Ok(Json(store.remove_object_by_key(bucket, &key).await?))
+}
+
 async fn remove_objects(
     State(AppState { store }): State<AppState>,
     Path(bucket): Path<Uuid>,
@@ -323,6 +755,14 @@ async fn remove_objects(
     Ok(Json(store.remove_objects(&bucket, &objects).await?))
 }
 
+async fn remove_part(
+    State(AppState { store }): State<AppState>,
+    Path(id): Path<Uuid>,
+) -> Result<StatusCode> {
+    store.remove_part(&id).await?;
+    Ok(StatusCode::NO_CONTENT)
+}
+
 async fn rename_bucket(
     State(AppState { store }): State<AppState>,
     Path((id, name)): Path<(Uuid, String)>,
@@ -337,6 +777,68 @@ async fn status(
     Ok(Json(store.get_totals().await?))
 }
 
+async fn stream_bucket_objects(
+    State(AppState { store }): State<AppState>,
+    Path(bucket): Path<Uuid>,
+) -> Response {
+    let stream = store.stream_bucket_objects(bucket).map(move |object| {
+        let object = object.map_err(|err| {
+            match &err {
+                fstore_core::Error::Sql(sql) => error!(
+                    "Failed to stream objects in bucket {bucket}: {err}: {sql}"
+                ),
+                _ => {
+                    error!("Failed to stream objects in bucket {bucket}: {err}")
+                }
+            }
+
+            io::Error::other(err)
+        })?;
+
+        let mut line = serde_json::to_vec(&object)?;
+        line.push(b'\n');
+
+        Ok::<_, io::Error>(Bytes::from(line))
+    });
+
+    ([(CONTENT_TYPE, NDJSON)], Body::from_stream(stream)).into_response()
+}
+
+async fn update_bucket(
+    State(AppState { store }): State<AppState>,
+    Path(id): Path<Uuid>,
+    Json(patch): Json<BucketPatch>,
+) -> Result<Json<Bucket>> {
+    Ok(Json(store.update_bucket(&id, &patch).await?))
+}
+
+async fn update_user_metadata(
+    State(AppState { store }): State<AppState>,
+    Path((bucket, object)): Path<(Uuid, Uuid)>,
+    Json(changes): Json<MetadataPatch>,
+) -> Result<Json<Metadata>> {
+    Ok(Json(
+        store.update_user_metadata(bucket, object, &changes).await?,
+    ))
+}
+
+async fn write_part(
+    State(AppState { store }): State<AppState>,
+    Path((id, offset)): Path<(Uuid, u64)>,
+    request: Request,
+) -> Result<Json<PartRange>> {
+    let mut part = store.get_ranged_part(&id).await?;
+
+    let written = part
+        .write_at(offset, request.into_body().into_data_stream())
+        .await?;
+
+    Ok(Json(PartRange {
+        start: offset,
+        end: offset + written,
+    }))
+}
+
 pub fn routes() -> Router<AppState> {
     Router::new()
         .route("/", get(about))
@@ -345,22 +847,52 @@ pub fn routes() -> Router<AppState> {
             get(get_bucket)
                 .put(add_bucket)
                 .post(add_object)
+                .patch(update_bucket)
                 .delete(remove_bucket),
         )
-        .route("/bucket/:name/objects", delete(remove_objects))
+        .route(
+            "/bucket/:name/objects",
+            get(get_object_page).delete(remove_objects),
+        )
+        .route("/bucket/:id/objects/stream", get(stream_bucket_objects))
         .route("/bucket/:id/:name", put(rename_bucket).post(clone_bucket))
+        .route(
+            presign::KEY,
+            get(get_key).head(head_key).put(put_key).delete(remove_key),
+        )
+        // Routes under a '-' segment cannot collide with the bucket name
+        // taken by `/bucket/:id/:name`.
+        .route("/bucket/:id/-/export", get(export_bucket))
+        .route("/bucket/:id/-/keys", get(get_keys))
+        .route("/bucket/:id/part/:part", put(commit_bucket_part))
+        .route("/bucket/:id/presign/*key", post(presign_key))
         .route("/buckets", get(get_buckets))
         .route("/object", post(new_part))
         .route("/object/:id", get(get_objects).post(append_part))
         .route(
             "/object/:bucket/:id",
             get(get_object_metadata)
+                .post(link_object)
                 .put(commit_part)
                 .delete(remove_object),
         )
-        .route("/object/:bucket/:object/data", get(get_object_data))
+        .route(
+            presign::OBJECT_DATA,
+            get(get_object_data).head(head_object_data),
+        )
+        .route(
+            "/object/:bucket/:object/metadata",
+            get(get_user_metadata).patch(update_user_metadata),
+        )
+        .route("/object/:bucket/:object/presign", post(presign_object))
         .route("/object/:bucket/all", get(get_all_objects))
+        .route("/object/:bucket/hash/:hash", post(link_object_by_hash))
         .route("/object/errors", get(get_object_errors))
+        .route("/object/hash/:hash", get(get_object_by_hash))
         .route("/objects", delete(prune))
+        .route("/part/:id", get(get_part).delete(remove_part))
+        .route("/part/:id/:offset", put(write_part))
+        .route("/part/:id/ranges", get(get_part_ranges))
+        .route("/parts", get(get_parts))
         .route("/status", get(status))
 }
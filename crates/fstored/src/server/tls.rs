@@ -0,0 +1,225 @@
+use crate::conf::Tls;
+
+use axum::Router;
+use hyper_util::{
+    rt::{TokioExecutor, TokioIo},
+    server::conn::auto,
+    service::TowerToHyperService,
+};
+use log::{debug, error, info};
+use rustls::{
+    crypto::ring,
+    pki_types::{CertificateDer, PrivateKeyDer},
+    server::WebPkiClientVerifier,
+    RootCertStore, ServerConfig,
+};
+use std::{
+    fs::File,
+    io::BufReader,
+    path::Path,
+    sync::{Arc, RwLock},
+    time::Duration,
+};
+use tokio::{net::TcpListener, task::JoinHandle, time::timeout};
+use tokio_rustls::TlsAcceptor;
+use tokio_util::{sync::CancellationToken, task::TaskTracker};
+
+const ALPN_PROTOCOLS: [&[u8]; 2] = [b"h2", b"http/1.1"];
+
+/// How long a client has to complete the TLS handshake after connecting.
+const HANDSHAKE_TIMEOUT: Duration = Duration::from_secs(10);
+
+fn open(path: &Path) -> Result<BufReader<File>, String> {
+    File::open(path)
+        .map(BufReader::new)
+        .map_err(|err| format!("Failed to open '{}': {err}", path.display()))
+}
+
+fn read_certificates(
+    path: &Path,
+) -> Result<Vec<CertificateDer<'static>>, String> {
+    let certificates = rustls_pemfile::certs(&mut open(path)?)
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|err| {
+            format!(
+                "Failed to read certificates from '{}': {err}",
+                path.display()
+            )
+        })?;
+
+    if certificates.is_empty() {
+        return Err(format!("No certificates found in '{}'", path.display()));
+    }
+
+    Ok(certificates)
+}
+
+fn read_key(path: &Path) -> Result<PrivateKeyDer<'static>, String> {
+    rustls_pemfile::private_key(&mut open(path)?)
+        .map_err(|err| {
+            format!(
+                "Failed to read private key from '{}': {err}",
+                path.display()
+            )
+        })?
+        .ok_or_else(|| format!("No private key found in '{}'", path.display()))
+}
+
+fn server_config(settings: &Tls) -> Result<ServerConfig, String> {
+    let provider = Arc::new(ring::default_provider());
+    let builder = ServerConfig::builder_with_provider(provider.clone())
+        .with_safe_default_protocol_versions()
+        .map_err(|err| format!("Failed to configure TLS: {err}"))?;
+
+    let builder = match &settings.client_ca {
+        Some(path) => {
+            let mut roots = RootCertStore::empty();
+
+            for certificate in read_certificates(path)? {
+                roots.add(certificate).map_err(|err| {
+                    format!(
+                        "Invalid client CA certificate in '{}': {err}",
+                        path.display()
+                    )
+                })?;
+            }
+
+            let verifier = WebPkiClientVerifier::builder_with_provider(
+                Arc::new(roots),
+                provider,
+            )
+            .build()
+            .map_err(|err| {
+                format!("Failed to configure client verification: {err}")
+            })?;
+
+            builder.with_client_cert_verifier(verifier)
+        }
+        None => builder.with_no_client_auth(),
+    };
+
+    let mut config = builder
+        .with_single_cert(
+            read_certificates(&settings.certificate)?,
+            read_key(&settings.key)?,
+        )
+        .map_err(|err| {
+            format!(
+                "Invalid certificate or key for '{}': {err}",
+                settings.certificate.display()
+            )
+        })?;
+
+    config.alpn_protocols = ALPN_PROTOCOLS
+        .iter()
+        .map(|protocol| protocol.to_vec())
+        .collect();
+
+    Ok(config)
+}
+
+/// TLS settings for a listener that can be reloaded while it is running.
+pub struct Acceptor {
+    settings: Tls,
+    config: RwLock<Arc<ServerConfig>>,
+}
+
+impl Acceptor {
+    pub fn new(settings: &Tls) -> Result<Self, String> {
+        Ok(Self {
+            settings: settings.clone(),
+            config: RwLock::new(Arc::new(server_config(settings)?)),
+        })
+    }
+
+    fn acceptor(&self) -> TlsAcceptor {
+        TlsAcceptor::from(self.config.read().unwrap().clone())
+    }
+
+    /// Reads the certificate, key and client CA files again. Connections
+    /// that are already established keep their current settings.
+    pub fn reload(&self) -> Result<(), String> {
+        let config = server_config(&self.settings)?;
+        *self.config.write().unwrap() = Arc::new(config);
+        Ok(())
+    }
+}
+
+/// Serves `app` over TLS on `address` until `token` is cancelled.
+pub async fn serve(
+    address: &str,
+    tls: Arc<Acceptor>,
+    app: Router,
+    token: CancellationToken,
+) -> Result<JoinHandle<()>, String> {
+    let listener = TcpListener::bind(address)
+        .await
+        .map_err(|err| format!("Failed to bind to '{address}': {err}"))?;
+
+    info!("Listening for TLS connections on {address}");
+
+    Ok(tokio::spawn(async move {
+        let tracker = TaskTracker::new();
+
+        loop {
+            let (stream, peer) = tokio::select! {
+                _ = token.cancelled() => break,
+                result = listener.accept() => match result {
+                    Ok(connection) => connection,
+                    Err(err) => {
+                        error!("Failed to accept connection: {err}");
+                        continue;
+                    }
+                },
+            };
+
+            let acceptor = tls.acceptor();
+            let app = app.clone();
+            let token = token.clone();
+
+            tracker.spawn(async move {
+                let handshake = tokio::select! {
+                    _ = token.cancelled() => return,
+                    result = timeout(
+                        HANDSHAKE_TIMEOUT,
+                        acceptor.accept(stream),
+                    ) => result,
+                };
+
+                let stream = match handshake {
+                    Ok(Ok(stream)) => stream,
+                    Ok(Err(err)) => {
+                        debug!("TLS handshake with {peer} failed: {err}");
+                        return;
+                    }
+                    Err(_) => {
+                        debug!("TLS handshake with {peer} timed out");
+                        return;
+                    }
+                };
+
+                let builder = auto::Builder::new(TokioExecutor::new());
+                let connection = builder.serve_connection_with_upgrades(
+                    TokioIo::new(stream),
+                    TowerToHyperService::new(app),
+                );
+                tokio::pin!(connection);
+
+                let result = tokio::select! {
+                    result = connection.as_mut() => result,
+                    _ = token.cancelled() => {
+                        connection.as_mut().graceful_shutdown();
+                        connection.await
+                    }
+                };
+
+                if let Err(err) = result {
+                    debug!("Connection with {peer} failed: {err}");
+                }
+            });
+        }
+
+        tracker.close();
+        tracker.wait().await;
+    }))
+}
@@ -4,11 +4,13 @@ SELECT
     name,
     date_created,
     count(object_id) AS object_count,
-    coalesce(sum(size), 0)::int8 AS space_used
+    coalesce(sum(size), 0)::int8 AS space_used,
+    description,
+    labels
 FROM data.bucket
 LEFT JOIN data.bucket_object USING (bucket_id)
 LEFT JOIN data.object USING (object_id)
-GROUP BY bucket_id, name;
+GROUP BY bucket_id, name, description, labels;
 
 CREATE VIEW bucket_contents AS
 SELECT
@@ -18,7 +20,10 @@ SELECT
     size,
     "type",
     subtype,
-    bucket_object.date_added
+    bucket_object.date_added,
+    key,
+    metadata,
+    filename
 FROM data.bucket_object
 JOIN data.object USING (object_id);
 
@@ -46,6 +51,15 @@ FROM data.object
 LEFT JOIN data.bucket_object bucket_objects USING (object_id)
 GROUP BY object_id;
 
+CREATE VIEW token AS
+SELECT
+    token_id,
+    name,
+    scope,
+    buckets,
+    date_created
+FROM data.token;
+
 CREATE TYPE remove_result AS (
     objects_removed bigint,
     space_freed     bigint
@@ -95,35 +109,51 @@ CREATE FUNCTION add_object(
     a_hash          text,
     a_size          bigint,
     a_type          text,
-    a_subtype       text
-) RETURNS SETOF object AS $$
+    a_subtype       text,
+    a_key           text,
+    a_metadata      jsonb,
+    a_filename      text
+) RETURNS SETOF bucket_contents AS $$
+DECLARE
+    id_for_hash     uuid;
 BEGIN
-    INSERT INTO data.bucket_object (
-        bucket_id,
-        object_id
-    ) VALUES (
-        a_bucket_id,
-        (
-            SELECT create_object(
-                a_object_id,
-                a_hash,
-                a_size,
-                a_type,
-                a_subtype
-            )
-        )
-    ) ON CONFLICT DO NOTHING;
+    id_for_hash := create_object(
+        a_object_id,
+        a_hash,
+        a_size,
+        a_type,
+        a_subtype
+    );
+
+    IF a_key IS NULL THEN
+        INSERT INTO data.bucket_object (
+            bucket_id,
+            object_id,
+            metadata,
+            filename
+        ) VALUES (
+            a_bucket_id,
+            id_for_hash,
+            a_metadata,
+            a_filename
+        ) ON CONFLICT (bucket_id, object_id) DO UPDATE
+        SET
+            metadata = bucket_object.metadata || excluded.metadata,
+            filename = coalesce(excluded.filename, bucket_object.filename);
+    ELSE
+        PERFORM put_object_key(
+            a_bucket_id,
+            a_key,
+            id_for_hash,
+            a_metadata,
+            a_filename
+        );
+    END IF;
 
     RETURN QUERY
-    SELECT
-        object_id,
-        hash,
-        size,
-        "type",
-        subtype,
-        date_added
+    SELECT *
     FROM bucket_contents
-    WHERE bucket_id = a_bucket_id AND hash = a_hash;
+    WHERE bucket_id = a_bucket_id AND object_id = id_for_hash;
 END;
 $$ LANGUAGE plpgsql;
 
@@ -136,8 +166,21 @@ BEGIN
         INSERT INTO data.bucket (name) VALUES (a_name)
         RETURNING bucket_id
     )
-    INSERT INTO data.bucket_object
-    SELECT (SELECT bucket_id FROM new_bucket), object_id, date_added
+    INSERT INTO data.bucket_object (
+        bucket_id,
+        object_id,
+        date_added,
+        key,
+        metadata,
+        filename
+    )
+    SELECT
+        (SELECT bucket_id FROM new_bucket),
+        object_id,
+        date_added,
+        key,
+        metadata,
+        filename
     FROM data.bucket_object
     WHERE bucket_id = a_original;
 
@@ -159,6 +202,25 @@ BEGIN
 END;
 $$ LANGUAGE plpgsql;
 
+CREATE FUNCTION create_token(
+    a_name          text,
+    a_secret_hash   text,
+    a_scope         text,
+    a_buckets       uuid[]
+) RETURNS SETOF token AS $$
+BEGIN
+    RETURN QUERY
+    INSERT INTO data.token (name, secret_hash, scope, buckets)
+    VALUES (a_name, a_secret_hash, a_scope, a_buckets)
+    RETURNING
+        token_id,
+        name,
+        scope,
+        buckets,
+        date_created;
+END;
+$$ LANGUAGE plpgsql;
+
 CREATE FUNCTION fetch_bucket(
     a_name          text
 ) RETURNS SETOF bucket AS $$
@@ -170,6 +232,17 @@ BEGIN
 END;
 $$ LANGUAGE plpgsql;
 
+CREATE FUNCTION fetch_bucket_by_id(
+    a_bucket_id     uuid
+) RETURNS SETOF bucket AS $$
+BEGIN
+    RETURN QUERY
+    SELECT *
+    FROM bucket
+    WHERE bucket_id = a_bucket_id;
+END;
+$$ LANGUAGE plpgsql;
+
 CREATE FUNCTION fetch_buckets(
     a_names         text[]
 ) RETURNS SETOF bucket AS $$
@@ -186,19 +259,24 @@ BEGIN
         name,
         date_created,
         object_count,
-        space_used
+        space_used,
+        description,
+        labels
     FROM names
     JOIN bucket USING (name)
     ORDER BY ordinality;
 END;
 $$ LANGUAGE plpgsql;
 
-CREATE FUNCTION fetch_buckets_all()
-RETURNS SETOF bucket AS $$
+CREATE FUNCTION fetch_buckets_all(
+    a_labels        jsonb,
+    a_label_names   text[]
+) RETURNS SETOF bucket AS $$
 BEGIN
     RETURN QUERY
     SELECT *
-    FROM bucket;
+    FROM bucket
+    WHERE labels @> a_labels AND labels ?& a_label_names;
 END;
 $$ LANGUAGE plpgsql;
 
@@ -213,6 +291,33 @@ BEGIN
 END;
 $$ LANGUAGE plpgsql;
 
+CREATE FUNCTION get_bucket_object(
+    a_bucket_id     uuid,
+    a_object_id     uuid
+) RETURNS SETOF bucket_contents AS $$
+BEGIN
+    RETURN QUERY
+    SELECT *
+    FROM bucket_contents
+    WHERE bucket_id = a_bucket_id AND object_id = a_object_id;
+END;
+$$ LANGUAGE plpgsql;
+
+CREATE FUNCTION get_bucket_contents(
+    a_bucket_id     uuid,
+    a_objects       uuid[]
+) RETURNS SETOF bucket_contents AS $$
+BEGIN
+    RETURN QUERY
+    SELECT *
+    FROM bucket_contents
+    WHERE
+        bucket_id = a_bucket_id AND
+        (a_objects IS NULL OR object_id = ANY(a_objects))
+    ORDER BY date_added, object_id;
+END;
+$$ LANGUAGE plpgsql;
+
 CREATE FUNCTION get_bucket_objects(a_bucket_id uuid)
 RETURNS SETOF object AS $$
 BEGIN
@@ -225,6 +330,50 @@ BEGIN
 END;
 $$ LANGUAGE plpgsql;
 
+CREATE FUNCTION get_bucket_objects_page(
+    a_bucket_id     uuid,
+    a_after_added   timestamptz,
+    a_after_id      uuid,
+    a_limit         bigint,
+    a_type          text,
+    a_subtype       text,
+    a_min_size      bigint,
+    a_max_size      bigint,
+    a_added_after   timestamptz,
+    a_added_before  timestamptz
+) RETURNS SETOF bucket_contents AS $$
+BEGIN
+    RETURN QUERY
+    SELECT *
+    FROM bucket_contents c
+    WHERE
+        c.bucket_id = a_bucket_id AND
+        (
+            a_after_added IS NULL OR
+            (c.date_added, c.object_id) > (a_after_added, a_after_id)
+        ) AND
+        (a_type IS NULL OR c."type" = a_type) AND
+        (a_subtype IS NULL OR c.subtype = a_subtype) AND
+        (a_min_size IS NULL OR c.size >= a_min_size) AND
+        (a_max_size IS NULL OR c.size <= a_max_size) AND
+        (a_added_after IS NULL OR c.date_added >= a_added_after) AND
+        (a_added_before IS NULL OR c.date_added < a_added_before)
+    ORDER BY c.date_added, c.object_id
+    LIMIT a_limit;
+END;
+$$ LANGUAGE plpgsql;
+
+CREATE FUNCTION get_errored_objects()
+RETURNS SETOF object AS $$
+BEGIN
+    RETURN QUERY
+    SELECT object.*
+    FROM object_error
+    JOIN object USING (object_id)
+    ORDER BY date_added DESC;
+END;
+$$ LANGUAGE plpgsql;
+
 CREATE FUNCTION get_errors()
 RETURNS SETOF object_error AS $$
 BEGIN
@@ -234,6 +383,59 @@ BEGIN
 END;
 $$ LANGUAGE plpgsql;
 
+CREATE FUNCTION get_key_prefixes(
+    a_bucket_id     uuid,
+    a_prefix        text,
+    a_delimiter     text
+) RETURNS SETOF text AS $$
+BEGIN
+    RETURN QUERY
+    SELECT DISTINCT
+        a_prefix ||
+        split_part(substr(key, length(a_prefix) + 1), a_delimiter, 1) ||
+        a_delimiter AS prefix
+    FROM data.bucket_object
+    WHERE
+        bucket_id = a_bucket_id AND
+        starts_with(key, a_prefix) AND
+        strpos(substr(key, length(a_prefix) + 1), a_delimiter) > 0
+    ORDER BY prefix;
+END;
+$$ LANGUAGE plpgsql;
+
+CREATE FUNCTION get_keyed_objects(
+    a_bucket_id     uuid,
+    a_prefix        text,
+    a_delimiter     text
+) RETURNS SETOF bucket_contents AS $$
+BEGIN
+    RETURN QUERY
+    SELECT *
+    FROM bucket_contents
+    WHERE
+        bucket_id = a_bucket_id AND
+        starts_with(key, a_prefix) AND (
+            a_delimiter IS NULL OR
+            strpos(substr(key, length(a_prefix) + 1), a_delimiter) = 0
+        )
+    ORDER BY key;
+END;
+$$ LANGUAGE plpgsql;
+
+CREATE FUNCTION get_missing_objects(a_objects uuid[])
+RETURNS SETOF uuid AS $$
+BEGIN
+    RETURN QUERY
+    SELECT unnest
+    FROM unnest(a_objects)
+    WHERE NOT EXISTS (
+        SELECT 1
+        FROM data.object
+        WHERE object_id = unnest
+    );
+END;
+$$ LANGUAGE plpgsql;
+
 CREATE FUNCTION get_objects(a_bucket_id uuid, a_objects uuid[])
 RETURNS SETOF object AS $$
 BEGIN
@@ -254,6 +456,28 @@ BEGIN
 END;
 $$ LANGUAGE plpgsql;
 
+CREATE FUNCTION get_object_by_hash(a_hash text)
+RETURNS SETOF object AS $$
+BEGIN
+    RETURN QUERY
+    SELECT *
+    FROM object
+    WHERE hash = a_hash;
+END;
+$$ LANGUAGE plpgsql;
+
+CREATE FUNCTION get_object_by_key(
+    a_bucket_id     uuid,
+    a_key           text
+) RETURNS SETOF bucket_contents AS $$
+BEGIN
+    RETURN QUERY
+    SELECT *
+    FROM bucket_contents
+    WHERE bucket_id = a_bucket_id AND key = a_key;
+END;
+$$ LANGUAGE plpgsql;
+
 CREATE FUNCTION get_object_count(before timestamptz) RETURNS SETOF bigint AS $$
 BEGIN
     RETURN QUERY
@@ -263,6 +487,131 @@ BEGIN
 END;
 $$ LANGUAGE plpgsql;
 
+CREATE FUNCTION get_token(a_secret_hash text) RETURNS SETOF token AS $$
+BEGIN
+    RETURN QUERY
+    SELECT
+        token_id,
+        name,
+        scope,
+        buckets,
+        date_created
+    FROM data.token
+    WHERE secret_hash = a_secret_hash;
+END;
+$$ LANGUAGE plpgsql;
+
+CREATE FUNCTION get_tokens() RETURNS SETOF token AS $$
+BEGIN
+    RETURN QUERY
+    SELECT *
+    FROM token
+    ORDER BY date_created;
+END;
+$$ LANGUAGE plpgsql;
+
+CREATE FUNCTION link_object(
+    a_bucket_id     uuid,
+    a_object_id     uuid
+) RETURNS SETOF object AS $$
+BEGIN
+    INSERT INTO data.bucket_object (bucket_id, object_id)
+    SELECT bucket_id, object_id
+    FROM data.bucket, data.object
+    WHERE bucket_id = a_bucket_id AND object_id = a_object_id
+    ON CONFLICT DO NOTHING;
+
+    RETURN QUERY
+    SELECT
+        object_id,
+        hash,
+        size,
+        "type",
+        subtype,
+        date_added
+    FROM bucket_contents
+    WHERE bucket_id = a_bucket_id AND object_id = a_object_id;
+END;
+$$ LANGUAGE plpgsql;
+
+CREATE FUNCTION link_object_by_hash(
+    a_bucket_id     uuid,
+    a_hash          text
+) RETURNS SETOF object AS $$
+BEGIN
+    RETURN QUERY
+    SELECT *
+    FROM link_object(
+        a_bucket_id,
+        (SELECT object_id FROM data.object WHERE hash = a_hash)
+    );
+END;
+$$ LANGUAGE plpgsql;
+
+CREATE FUNCTION put_object_key(
+    a_bucket_id     uuid,
+    a_key           text,
+    a_object_id     uuid,
+    a_metadata      jsonb,
+    a_filename      text
+) RETURNS SETOF bucket_contents AS $$
+DECLARE
+    existing_key    text;
+BEGIN
+    SELECT key INTO existing_key
+    FROM data.bucket_object
+    WHERE bucket_id = a_bucket_id AND object_id = a_object_id
+    FOR UPDATE;
+
+    IF existing_key <> a_key THEN
+        RAISE unique_violation USING MESSAGE = format(
+            'Object %s is already stored in this bucket under key ''%s''',
+            a_object_id,
+            existing_key
+        );
+    END IF;
+
+    DELETE FROM data.bucket_object
+    WHERE
+        bucket_id = a_bucket_id AND
+        key = a_key AND
+        object_id <> a_object_id;
+
+    INSERT INTO data.bucket_object (
+        bucket_id,
+        object_id,
+        key,
+        metadata,
+        filename
+    )
+    SELECT bucket_id, object_id, a_key, a_metadata, a_filename
+    FROM data.bucket, data.object
+    WHERE bucket_id = a_bucket_id AND object_id = a_object_id
+    ON CONFLICT (bucket_id, object_id) DO UPDATE
+    SET
+        key = excluded.key,
+        metadata = bucket_object.metadata || excluded.metadata,
+        filename = coalesce(excluded.filename, bucket_object.filename);
+
+    RETURN QUERY
+    SELECT *
+    FROM bucket_contents
+    WHERE bucket_id = a_bucket_id AND key = a_key;
+END;
+$$ LANGUAGE plpgsql;
+
+CREATE FUNCTION stream_bucket_objects(
+    a_bucket_id     uuid
+) RETURNS SETOF bucket_contents AS $$
+BEGIN
+    RETURN QUERY
+    SELECT *
+    FROM bucket_contents
+    WHERE bucket_id = a_bucket_id
+    ORDER BY date_added, object_id;
+END;
+$$ LANGUAGE plpgsql;
+
 CREATE FUNCTION stream_objects(before timestamptz) RETURNS SETOF object AS $$
 BEGIN
     RETURN QUERY
@@ -305,6 +654,33 @@ BEGIN
 END;
 $$ LANGUAGE plpgsql;
 
+CREATE FUNCTION remove_object_by_key(
+    a_bucket_id     uuid,
+    a_key           text
+) RETURNS SETOF bucket_contents AS $$
+BEGIN
+    RETURN QUERY
+    WITH deleted AS (
+        DELETE FROM data.bucket_object
+        WHERE bucket_id = a_bucket_id AND key = a_key
+        RETURNING bucket_id, object_id, date_added, key, metadata, filename
+    )
+    SELECT
+        bucket_id,
+        object_id,
+        hash,
+        size,
+        "type",
+        subtype,
+        deleted.date_added,
+        deleted.key,
+        deleted.metadata,
+        deleted.filename
+    FROM deleted
+    JOIN data.object USING (object_id);
+END;
+$$ LANGUAGE plpgsql;
+
 CREATE FUNCTION remove_objects(
     a_bucket_id     uuid,
     a_objects       uuid[]
@@ -340,6 +716,20 @@ BEGIN
 END;
 $$ LANGUAGE plpgsql;
 
+CREATE FUNCTION remove_token(a_token_id uuid) RETURNS SETOF token AS $$
+BEGIN
+    RETURN QUERY
+    DELETE FROM data.token
+    WHERE token_id = a_token_id
+    RETURNING
+        token_id,
+        name,
+        scope,
+        buckets,
+        date_created;
+END;
+$$ LANGUAGE plpgsql;
+
 CREATE FUNCTION rename_bucket(
     a_bucket_id     uuid,
     a_bucket_name   text
@@ -351,6 +741,29 @@ BEGIN
 END;
 $$ LANGUAGE plpgsql;
 
+CREATE FUNCTION update_bucket(
+    a_bucket_id     uuid,
+    a_description   text,
+    a_set           jsonb,
+    a_remove        text[]
+) RETURNS SETOF bucket AS $$
+BEGIN
+    UPDATE data.bucket
+    SET
+        description = CASE
+            WHEN a_description IS NULL THEN description
+            ELSE nullif(a_description, '')
+        END,
+        labels = (labels - a_remove) || a_set
+    WHERE bucket_id = a_bucket_id;
+
+    RETURN QUERY
+    SELECT *
+    FROM bucket
+    WHERE bucket_id = a_bucket_id;
+END;
+$$ LANGUAGE plpgsql;
+
 CREATE FUNCTION update_object_errors(records object_error[])
 RETURNS void AS $$
 BEGIN
@@ -372,3 +785,21 @@ BEGIN
     ON CONFLICT (object_id) DO UPDATE SET message = excluded.message;
 END;
 $$ LANGUAGE plpgsql;
+
+CREATE FUNCTION update_object_metadata(
+    a_bucket_id     uuid,
+    a_object_id     uuid,
+    a_set           jsonb,
+    a_remove        text[]
+) RETURNS SETOF bucket_contents AS $$
+BEGIN
+    UPDATE data.bucket_object
+    SET metadata = (metadata - a_remove) || a_set
+    WHERE bucket_id = a_bucket_id AND object_id = a_object_id;
+
+    RETURN QUERY
+    SELECT *
+    FROM bucket_contents
+    WHERE bucket_id = a_bucket_id AND object_id = a_object_id;
+END;
+$$ LANGUAGE plpgsql;
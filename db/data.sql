@@ -6,9 +6,17 @@ CREATE TABLE bucket (
     name            text UNIQUE NOT NULL,
 
     -- The time this bucket was first created.
-    date_created    timestamptz NOT NULL DEFAULT NOW()
+    date_created    timestamptz NOT NULL DEFAULT NOW(),
+
+    -- A user-provided summary of the bucket's purpose.
+    description     text,
+
+    -- User-defined string labels, such as the bucket's owner.
+    labels          jsonb NOT NULL DEFAULT '{}'
 );
 
+CREATE INDEX bucket_labels_idx ON bucket USING gin (labels);
+
 CREATE TABLE object (
     -- The object's unique identifier.
     object_id       uuid PRIMARY KEY,
@@ -27,15 +35,60 @@ CREATE TABLE object (
     date_added      timestamptz NOT NULL DEFAULT NOW()
 );
 
+-- The size of user-defined metadata in bytes: the total length of its
+-- names and values.
+CREATE FUNCTION metadata_size(metadata jsonb) RETURNS bigint AS $$
+    SELECT coalesce(sum(octet_length(key) + octet_length(value)), 0)
+    FROM jsonb_each_text(metadata);
+$$ LANGUAGE sql IMMUTABLE;
+
 CREATE TABLE bucket_object (
     bucket_id       uuid REFERENCES bucket ON DELETE CASCADE,
     object_id       uuid REFERENCES object ON DELETE CASCADE,
     date_added      timestamptz NOT NULL DEFAULT NOW(),
 
-    PRIMARY KEY (bucket_id, object_id)
+    -- An optional user-assigned, path-like name for the object.
+    key             text,
+
+    -- User-defined string properties of the object within this bucket.
+    metadata        jsonb NOT NULL DEFAULT '{}'
+                    CONSTRAINT bucket_object_metadata_size
+                    CHECK (metadata_size(metadata) <= 8192),
+
+    -- The name of the file the object was uploaded from, suggested to
+    -- clients when they download it.
+    filename        text,
+
+    PRIMARY KEY (bucket_id, object_id),
+    UNIQUE (bucket_id, key)
 );
 
+CREATE INDEX bucket_object_date_added_idx
+ON bucket_object (bucket_id, date_added, object_id);
+
 CREATE TABLE object_error (
     object_id       uuid PRIMARY KEY REFERENCES object ON DELETE CASCADE,
     message         text NOT NULL
 );
+
+CREATE TABLE token (
+    -- The token's unique identifier.
+    token_id        uuid DEFAULT gen_random_uuid() PRIMARY KEY,
+
+    -- A user-assigned name describing who or what uses the token.
+    name            text NOT NULL,
+
+    -- SHA256 checksum of the token's secret. The secret itself is only
+    -- shown once when the token is created.
+    secret_hash     text UNIQUE NOT NULL,
+
+    -- The operations the token permits: 'read', 'write' or 'admin'.
+    scope           text NOT NULL
+                    CHECK (scope IN ('read', 'write', 'admin')),
+
+    -- The buckets the token may access, or NULL for all buckets.
+    buckets         uuid[],
+
+    -- The time this token was created.
+    date_created    timestamptz NOT NULL DEFAULT NOW()
+);
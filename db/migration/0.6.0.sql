@@ -0,0 +1,49 @@
+ALTER TABLE bucket_object
+ADD COLUMN key text;
+
+ALTER TABLE bucket_object
+ADD UNIQUE (bucket_id, key);
+
+CREATE INDEX bucket_object_date_added_idx
+ON bucket_object (bucket_id, date_added, object_id);
+
+CREATE FUNCTION metadata_size(metadata jsonb) RETURNS bigint AS $$
+    SELECT coalesce(sum(octet_length(key) + octet_length(value)), 0)
+    FROM jsonb_each_text(metadata);
+$$ LANGUAGE sql IMMUTABLE;
+
+ALTER TABLE bucket_object
+ADD COLUMN metadata jsonb NOT NULL DEFAULT '{}'
+CONSTRAINT bucket_object_metadata_size
+CHECK (metadata_size(metadata) <= 8192);
+
+ALTER TABLE bucket_object
+ADD COLUMN filename text;
+
+ALTER TABLE bucket
+ADD COLUMN description text,
+ADD COLUMN labels jsonb NOT NULL DEFAULT '{}';
+
+CREATE INDEX bucket_labels_idx ON bucket USING gin (labels);
+
+CREATE TABLE token (
+    -- The token's unique identifier.
+    token_id        uuid DEFAULT gen_random_uuid() PRIMARY KEY,
+
+    -- A user-assigned name describing who or what uses the token.
+    name            text NOT NULL,
+
+    -- SHA256 checksum of the token's secret. The secret itself is only
+    -- shown once when the token is created.
+    secret_hash     text UNIQUE NOT NULL,
+
+    -- The operations the token permits: 'read', 'write' or 'admin'.
+    scope           text NOT NULL
+                    CHECK (scope IN ('read', 'write', 'admin')),
+
+    -- The buckets the token may access, or NULL for all buckets.
+    buckets         uuid[],
+
+    -- The time this token was created.
+    date_created    timestamptz NOT NULL DEFAULT NOW()
+);